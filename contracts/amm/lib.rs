@@ -0,0 +1,821 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::pair::{Pair, PairRef, MINIMUM_LIQUIDITY, SWAP_FEE_BPS};
+
+#[ink::contract]
+mod pair {
+    use iamm::{Error, IFlashSwapCallee, IPair, Result};
+    use ierc20::IERC20;
+    use ink::codegen::TraitCallBuilder;
+    use ink::prelude::vec::Vec;
+    use ink::storage::Mapping;
+    use primitive_types::{U256, U512};
+
+    /// Liquidity locked forever by the first deposit, so the pair can never be emptied.
+    pub const MINIMUM_LIQUIDITY: u128 = 1_000;
+    /// The fee charged on the input of every swap, in basis points (0.3%).
+    pub const SWAP_FEE_BPS: u128 = 30;
+    const BPS: u128 = 10_000;
+
+    #[ink(storage)]
+    pub struct Pair {
+        /// The first token of the pair.
+        token_0: AccountId,
+        /// The second token of the pair.
+        token_1: AccountId,
+        /// Amount of `token_0` accounted for by the pair.
+        reserve_0: Balance,
+        /// Amount of `token_1` accounted for by the pair.
+        reserve_1: Balance,
+        /// Total liquidity issued, including `MINIMUM_LIQUIDITY`.
+        total_liquidity: Balance,
+        /// Liquidity held by each provider.
+        liquidity: Mapping<AccountId, Balance>,
+    }
+
+    /// Emitted when a provider adds liquidity.
+    #[ink(event)]
+    pub struct Mint {
+        #[ink(topic)]
+        provider: AccountId,
+        amount_0: Balance,
+        amount_1: Balance,
+        liquidity: Balance,
+    }
+
+    /// Emitted when a provider removes liquidity.
+    #[ink(event)]
+    pub struct Burn {
+        #[ink(topic)]
+        provider: AccountId,
+        amount_0: Balance,
+        amount_1: Balance,
+        liquidity: Balance,
+    }
+
+    /// Emitted on every swap and flash swap.
+    #[ink(event)]
+    pub struct Swap {
+        #[ink(topic)]
+        sender: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        amount_0_in: Balance,
+        amount_1_in: Balance,
+        amount_0_out: Balance,
+        amount_1_out: Balance,
+    }
+
+    /// Emitted whenever the reserves are updated.
+    #[ink(event)]
+    pub struct Sync {
+        reserve_0: Balance,
+        reserve_1: Balance,
+    }
+
+    impl IPair for Pair {
+        /// Returns the first token of the pair.
+        #[ink(message)]
+        fn token_0(&self) -> AccountId {
+            self.token_0
+        }
+
+        /// Returns the second token of the pair.
+        #[ink(message)]
+        fn token_1(&self) -> AccountId {
+            self.token_1
+        }
+
+        /// Returns the reserves of `token_0` and `token_1`, in that order.
+        #[ink(message)]
+        fn get_reserves(&self) -> (Balance, Balance) {
+            (self.reserve_0, self.reserve_1)
+        }
+
+        /// Returns the amount of the other token received for `amount_in` of `token_in`,
+        /// after the swap fee.
+        ///
+        /// ## Errors:
+        /// - `InvalidToken` if `token_in` is not part of the pair.
+        #[ink(message)]
+        fn get_amount_out(&self, token_in: AccountId, amount_in: Balance) -> Result<Balance> {
+            let (reserve_in, reserve_out) = self._reserves_for(token_in)?;
+            Self::amount_out(amount_in, reserve_in, reserve_out)
+        }
+
+        /// Swaps `amount_in` of `token_in`, pulled from the caller, for the other token.
+        ///
+        /// ## Parameters:
+        /// - `token_in`: The token sold.
+        /// - `amount_in`: The amount of `token_in` sold.
+        /// - `min_amount_out`: The minimum amount of the other token to receive.
+        /// - `to`: The recipient of the bought tokens.
+        ///
+        /// ## Returns:
+        /// - The amount of the other token sent to `to`.
+        ///
+        /// ## Errors:
+        /// - `InvalidToken` if `token_in` is not part of the pair.
+        /// - `InvalidTo` if `to` is one of the pair tokens.
+        /// - `InsufficientInputAmount` if `amount_in` is zero.
+        /// - `SlippageExceeded` if the swap would send less than `min_amount_out`.
+        /// - `ERC20Error` if pulling the input or sending the output fails.
+        #[ink(message)]
+        fn swap(
+            &mut self,
+            token_in: AccountId,
+            amount_in: Balance,
+            min_amount_out: Balance,
+            to: AccountId,
+        ) -> Result<Balance> {
+            self._ensure_valid_to(to)?;
+            let (reserve_in, reserve_out) = self._reserves_for(token_in)?;
+            if amount_in == 0 {
+                return Err(Error::InsufficientInputAmount);
+            }
+            let amount_out = Self::amount_out(amount_in, reserve_in, reserve_out)?;
+            if amount_out < min_amount_out {
+                return Err(Error::SlippageExceeded {
+                    amount_out,
+                    min_amount_out,
+                });
+            }
+            if amount_out == 0 {
+                return Err(Error::InsufficientOutputAmount);
+            }
+
+            let caller = self.env().caller();
+            let token_out = if token_in == self.token_0 {
+                self.token_1
+            } else {
+                self.token_0
+            };
+            self._call_erc20_transfer_from(token_in, caller, amount_in)?;
+            self._call_erc20_transfer(token_out, to, amount_out)?;
+            self._sync();
+
+            let (amount_0_in, amount_1_in, amount_0_out, amount_1_out) = if token_in == self.token_0
+            {
+                (amount_in, 0, 0, amount_out)
+            } else {
+                (0, amount_in, amount_out, 0)
+            };
+            self.env().emit_event(Swap {
+                sender: caller,
+                to,
+                amount_0_in,
+                amount_1_in,
+                amount_0_out,
+                amount_1_out,
+            });
+            Ok(amount_out)
+        }
+
+        /// Optimistically sends the requested tokens to `to`, calls it back when
+        /// `data` is not empty, and checks that the pair was paid back.
+        ///
+        /// The callee is called without allowing reentry, so the pair cannot be
+        /// used again until the flash swap is settled.
+        ///
+        /// ## Parameters:
+        /// - `amount_0_out`: The amount of `token_0` sent to `to`.
+        /// - `amount_1_out`: The amount of `token_1` sent to `to`.
+        /// - `to`: The recipient of the tokens.
+        /// - `data`: Arbitrary data forwarded to the callback.
+        ///
+        /// ## Errors:
+        /// - `InsufficientOutputAmount` if both amounts are zero.
+        /// - `InsufficientLiquidity` if an amount is not below its reserve.
+        /// - `InvalidTo` if `to` is one of the pair tokens.
+        /// - `CallbackFailed` if the callback failed.
+        /// - `InsufficientInputAmount` if nothing was paid back.
+        /// - `KInvariant` if the payment does not cover the swap and its fee.
+        #[ink(message)]
+        fn flash_swap(
+            &mut self,
+            amount_0_out: Balance,
+            amount_1_out: Balance,
+            to: AccountId,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            if amount_0_out == 0 && amount_1_out == 0 {
+                return Err(Error::InsufficientOutputAmount);
+            }
+            if amount_0_out >= self.reserve_0 || amount_1_out >= self.reserve_1 {
+                return Err(Error::InsufficientLiquidity);
+            }
+            self._ensure_valid_to(to)?;
+
+            if amount_0_out > 0 {
+                self._call_erc20_transfer(self.token_0, to, amount_0_out)?;
+            }
+            if amount_1_out > 0 {
+                self._call_erc20_transfer(self.token_1, to, amount_1_out)?;
+            }
+            let caller = self.env().caller();
+            if !data.is_empty() {
+                self._call_flash_swap_callee(to, caller, amount_0_out, amount_1_out, data)?;
+            }
+
+            let balance_0 = self._call_erc20_balance_of(self.token_0);
+            let balance_1 = self._call_erc20_balance_of(self.token_1);
+            let amount_0_in = balance_0.saturating_sub(self.reserve_0 - amount_0_out);
+            let amount_1_in = balance_1.saturating_sub(self.reserve_1 - amount_1_out);
+            if amount_0_in == 0 && amount_1_in == 0 {
+                return Err(Error::InsufficientInputAmount);
+            }
+            let adjusted_0 = Self::adjusted_balance(balance_0, amount_0_in)?;
+            let adjusted_1 = Self::adjusted_balance(balance_1, amount_1_in)?;
+            let k_after = adjusted_0.full_mul(adjusted_1);
+            let k_before = U256::from(self.reserve_0)
+                .full_mul(U256::from(self.reserve_1) * U256::from(BPS * BPS));
+            if k_after < k_before {
+                return Err(Error::KInvariant);
+            }
+            self._update(balance_0, balance_1);
+
+            self.env().emit_event(Swap {
+                sender: caller,
+                to,
+                amount_0_in,
+                amount_1_in,
+                amount_0_out,
+                amount_1_out,
+            });
+            Ok(())
+        }
+    }
+
+    impl Pair {
+        /// Creates an empty pair of `token_0` and `token_1`.
+        ///
+        /// ## Parameters:
+        /// - `token_0`: The first token of the pair.
+        /// - `token_1`: The second token of the pair. Must differ from `token_0`.
+        #[ink(constructor)]
+        pub fn new(token_0: AccountId, token_1: AccountId) -> Self {
+            assert!(token_0 != token_1, "identical pair tokens");
+            Self {
+                token_0,
+                token_1,
+                reserve_0: 0,
+                reserve_1: 0,
+                total_liquidity: 0,
+                liquidity: Mapping::default(),
+            }
+        }
+
+        /// Returns the total liquidity issued.
+        #[ink(message)]
+        pub fn total_liquidity(&self) -> Balance {
+            self.total_liquidity
+        }
+
+        /// Returns the liquidity held by `provider`.
+        #[ink(message)]
+        pub fn liquidity_of(&self, provider: AccountId) -> Balance {
+            self.liquidity.get(provider).unwrap_or_default()
+        }
+
+        /// Adds liquidity to the pair, pulling the tokens from the caller.
+        ///
+        /// The first deposit sets the price. Later deposits use as much of the
+        /// desired amounts as the current price allows, so the caller never pays
+        /// more than `amount_0_desired` and `amount_1_desired`.
+        ///
+        /// ## Parameters:
+        /// - `amount_0_desired`: The maximum amount of `token_0` to deposit.
+        /// - `amount_1_desired`: The maximum amount of `token_1` to deposit.
+        /// - `min_liquidity`: The minimum liquidity to receive.
+        ///
+        /// ## Returns:
+        /// - The liquidity minted to the caller.
+        ///
+        /// ## Errors:
+        /// - `InsufficientLiquidityMinted` if less than `min_liquidity`, or nothing, is minted.
+        /// - `ERC20Error` if pulling the tokens fails.
+        /// - `Overflow` if an arithmetic operation overflows.
+        #[ink(message)]
+        pub fn add_liquidity(
+            &mut self,
+            amount_0_desired: Balance,
+            amount_1_desired: Balance,
+            min_liquidity: Balance,
+        ) -> Result<Balance> {
+            let (amount_0, amount_1) = if self.reserve_0 == 0 && self.reserve_1 == 0 {
+                (amount_0_desired, amount_1_desired)
+            } else {
+                let amount_1_optimal =
+                    Self::mul_div(amount_0_desired, self.reserve_1, self.reserve_0)?;
+                if amount_1_optimal <= amount_1_desired {
+                    (amount_0_desired, amount_1_optimal)
+                } else {
+                    let amount_0_optimal =
+                        Self::mul_div(amount_1_desired, self.reserve_0, self.reserve_1)?;
+                    (amount_0_optimal, amount_1_desired)
+                }
+            };
+
+            let liquidity =
+                if self.total_liquidity == 0 {
+                    let product = U256::from(amount_0) * U256::from(amount_1);
+                    let liquidity = Self::sqrt(product)
+                        .checked_sub(MINIMUM_LIQUIDITY)
+                        .ok_or(Error::InsufficientLiquidityMinted)?;
+                    self.total_liquidity = MINIMUM_LIQUIDITY;
+                    liquidity
+                } else {
+                    Self::mul_div(amount_0, self.total_liquidity, self.reserve_0)?.min(
+                        Self::mul_div(amount_1, self.total_liquidity, self.reserve_1)?,
+                    )
+                };
+            if liquidity == 0 || liquidity < min_liquidity {
+                return Err(Error::InsufficientLiquidityMinted);
+            }
+
+            let provider = self.env().caller();
+            self._call_erc20_transfer_from(self.token_0, provider, amount_0)?;
+            self._call_erc20_transfer_from(self.token_1, provider, amount_1)?;
+            self.total_liquidity += liquidity;
+            self.liquidity
+                .insert(provider, &(self.liquidity_of(provider) + liquidity));
+            self._sync();
+
+            self.env().emit_event(Mint {
+                provider,
+                amount_0,
+                amount_1,
+                liquidity,
+            });
+            Ok(liquidity)
+        }
+
+        /// Removes `liquidity` from the pair, sending the caller its share of both tokens.
+        ///
+        /// ## Parameters:
+        /// - `liquidity`: The liquidity to burn.
+        /// - `min_amount_0`: The minimum amount of `token_0` to receive.
+        /// - `min_amount_1`: The minimum amount of `token_1` to receive.
+        ///
+        /// ## Returns:
+        /// - The amounts of `token_0` and `token_1` sent to the caller.
+        ///
+        /// ## Errors:
+        /// - `InsufficientLiquidityBurned` if the caller holds less than `liquidity`,
+        ///   or would receive nothing or less than the minimums.
+        /// - `ERC20Error` if sending the tokens fails.
+        /// - `Overflow` if an arithmetic operation overflows.
+        #[ink(message)]
+        pub fn remove_liquidity(
+            &mut self,
+            liquidity: Balance,
+            min_amount_0: Balance,
+            min_amount_1: Balance,
+        ) -> Result<(Balance, Balance)> {
+            let provider = self.env().caller();
+            let held = self.liquidity_of(provider);
+            if liquidity == 0 || held < liquidity {
+                return Err(Error::InsufficientLiquidityBurned);
+            }
+
+            let balance_0 = self._call_erc20_balance_of(self.token_0);
+            let balance_1 = self._call_erc20_balance_of(self.token_1);
+            let amount_0 = Self::mul_div(liquidity, balance_0, self.total_liquidity)?;
+            let amount_1 = Self::mul_div(liquidity, balance_1, self.total_liquidity)?;
+            if amount_0 == 0 || amount_1 == 0 {
+                return Err(Error::InsufficientLiquidityBurned);
+            }
+            if amount_0 < min_amount_0 || amount_1 < min_amount_1 {
+                return Err(Error::InsufficientLiquidityBurned);
+            }
+
+            self.liquidity.insert(provider, &(held - liquidity));
+            self.total_liquidity -= liquidity;
+            self._call_erc20_transfer(self.token_0, provider, amount_0)?;
+            self._call_erc20_transfer(self.token_1, provider, amount_1)?;
+            self._sync();
+
+            self.env().emit_event(Burn {
+                provider,
+                amount_0,
+                amount_1,
+                liquidity,
+            });
+            Ok((amount_0, amount_1))
+        }
+
+        /// Returns the output of selling `amount_in` against the given reserves,
+        /// charging `SWAP_FEE_BPS` on the input.
+        fn amount_out(
+            amount_in: Balance,
+            reserve_in: Balance,
+            reserve_out: Balance,
+        ) -> Result<Balance> {
+            if reserve_in == 0 || reserve_out == 0 {
+                return Err(Error::InsufficientLiquidity);
+            }
+            let amount_in_with_fee = U256::from(amount_in) * U256::from(BPS - SWAP_FEE_BPS);
+            let numerator = amount_in_with_fee.full_mul(U256::from(reserve_out));
+            let denominator = U256::from(reserve_in) * U256::from(BPS) + amount_in_with_fee;
+            let amount_out = numerator / U512::from(denominator);
+            // The output is always below `reserve_out`, so it fits a `Balance`.
+            Ok(amount_out.low_u128())
+        }
+
+        /// Returns `balance` scaled by `BPS`, minus the swap fee on `amount_in`.
+        fn adjusted_balance(balance: Balance, amount_in: Balance) -> Result<U256> {
+            let scaled = U256::from(balance) * U256::from(BPS);
+            let fee = U256::from(amount_in) * U256::from(SWAP_FEE_BPS);
+            scaled.checked_sub(fee).ok_or(Error::Overflow)
+        }
+
+        /// Computes `a * b / c` at full precision, failing with `Overflow` if the
+        /// result does not fit a `u128`.
+        fn mul_div(a: u128, b: u128, c: u128) -> Result<u128> {
+            let result = U256::from(a) * U256::from(b) / U256::from(c);
+            u128::try_from(result).map_err(|_| Error::Overflow)
+        }
+
+        /// Integer square root, rounded down.
+        fn sqrt(value: U256) -> u128 {
+            if value < U256::from(2) {
+                return value.low_u128();
+            }
+            let mut x = value;
+            let mut y = value / 2 + 1;
+            while y < x {
+                x = y;
+                y = (x + value / x) / 2;
+            }
+            // The root of a 256-bit value fits in 128 bits.
+            x.low_u128()
+        }
+
+        /// Returns the reserves of `token_in` and of the other token.
+        fn _reserves_for(&self, token_in: AccountId) -> Result<(Balance, Balance)> {
+            if token_in == self.token_0 {
+                Ok((self.reserve_0, self.reserve_1))
+            } else if token_in == self.token_1 {
+                Ok((self.reserve_1, self.reserve_0))
+            } else {
+                Err(Error::InvalidToken)
+            }
+        }
+
+        /// Returns `InvalidTo` if `to` is one of the pair tokens.
+        fn _ensure_valid_to(&self, to: AccountId) -> Result<()> {
+            if to == self.token_0 || to == self.token_1 {
+                return Err(Error::InvalidTo);
+            }
+            Ok(())
+        }
+
+        /// Sets the reserves to the current token balances of the pair.
+        fn _sync(&mut self) {
+            let balance_0 = self._call_erc20_balance_of(self.token_0);
+            let balance_1 = self._call_erc20_balance_of(self.token_1);
+            self._update(balance_0, balance_1);
+        }
+
+        /// Sets the reserves and emits a `Sync` event.
+        fn _update(&mut self, balance_0: Balance, balance_1: Balance) {
+            self.reserve_0 = balance_0;
+            self.reserve_1 = balance_1;
+            self.env().emit_event(Sync {
+                reserve_0: balance_0,
+                reserve_1: balance_1,
+            });
+        }
+
+        /// Calls the ERC20 `balance_of` function for the pair on a given token contract.
+        ///
+        /// ## Params:
+        /// - `token`: AccountId of the ERC20 contract.
+        ///
+        /// ## Returns:
+        /// - The balance of the pair as `u128`.
+        fn _call_erc20_balance_of(&self, token: AccountId) -> Balance {
+            let erc20: ink::contract_ref!(IERC20) = token.into();
+            erc20.balance_of(self.env().account_id())
+        }
+
+        /// Calls the ERC20 `transfer` function on a given token contract.
+        ///
+        /// ## Params:
+        /// - `token`: AccountId of the ERC20 contract.
+        /// - `to`: AccountId that will receive the tokens.
+        /// - `amount`: Amount of tokens to transfer.
+        fn _call_erc20_transfer(
+            &self,
+            token: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<()> {
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            erc20.transfer(to, amount).map_err(Error::ERC20Error)?;
+            Ok(())
+        }
+
+        /// Calls the ERC20 `transfer_from` function on a given token contract,
+        /// moving `amount` tokens from `from` to the pair.
+        ///
+        /// ## Params:
+        /// - `token`: AccountId of the ERC20 contract.
+        /// - `from`: AccountId whose tokens are pulled.
+        /// - `amount`: Amount of tokens to transfer.
+        fn _call_erc20_transfer_from(
+            &self,
+            token: AccountId,
+            from: AccountId,
+            amount: Balance,
+        ) -> Result<()> {
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            erc20
+                .transfer_from(from, self.env().account_id(), amount)
+                .map_err(Error::ERC20Error)?;
+            Ok(())
+        }
+
+        /// Calls the `on_flash_swap` callback on an `IFlashSwapCallee` contract.
+        ///
+        /// ## Params:
+        /// - `callee`: AccountId of the contract receiving the tokens.
+        /// - `initiator`: AccountId that requested the flash swap.
+        /// - `amount_0`: Amount of `token_0` sent.
+        /// - `amount_1`: Amount of `token_1` sent.
+        /// - `data`: Arbitrary data forwarded to the callee.
+        fn _call_flash_swap_callee(
+            &self,
+            callee: AccountId,
+            initiator: AccountId,
+            amount_0: Balance,
+            amount_1: Balance,
+            data: Vec<u8>,
+        ) -> Result<()> {
+            let mut callee: ink::contract_ref!(IFlashSwapCallee) = callee.into();
+            callee
+                .call_mut()
+                .on_flash_swap(initiator, amount_0, amount_1, data)
+                .try_invoke()
+                .map_err(|_| Error::CallbackFailed)?
+                .map_err(|_| Error::CallbackFailed)?
+                .map_err(|_| Error::CallbackFailed)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn amount_out_charges_the_swap_fee() {
+            // 1_000 in, less 0.3%, against a 100_000/100_000 pool.
+            assert_eq!(Pair::amount_out(1_000, 100_000, 100_000), Ok(987));
+            assert_eq!(Pair::amount_out(0, 100_000, 100_000), Ok(0));
+            assert_eq!(
+                Pair::amount_out(1_000, 0, 100_000),
+                Err(Error::InsufficientLiquidity)
+            );
+        }
+
+        #[ink::test]
+        fn amount_out_handles_large_reserves() {
+            let reserve = u128::MAX / 2;
+            let amount_out = Pair::amount_out(reserve, reserve, reserve).unwrap();
+            // Doubling the input reserve pays out just under half of the output.
+            assert!(amount_out < reserve / 2);
+            assert!(amount_out > reserve / 2 - reserve / 500);
+
+            assert!(Pair::amount_out(u128::MAX, 1, u128::MAX).unwrap() < u128::MAX);
+        }
+
+        #[ink::test]
+        fn sqrt_rounds_down() {
+            assert_eq!(Pair::sqrt(U256::zero()), 0);
+            assert_eq!(Pair::sqrt(U256::one()), 1);
+            assert_eq!(Pair::sqrt(U256::from(15)), 3);
+            assert_eq!(Pair::sqrt(U256::from(16)), 4);
+            assert_eq!(
+                Pair::sqrt(U256::from(u128::MAX) * U256::from(u128::MAX)),
+                u128::MAX
+            );
+            assert_eq!(Pair::sqrt(U256::MAX), u128::MAX);
+        }
+
+        #[ink::test]
+        fn adjusted_balance_deducts_the_fee_on_the_input() {
+            assert_eq!(
+                Pair::adjusted_balance(1_000, 100),
+                Ok(U256::from(1_000 * BPS - 100 * SWAP_FEE_BPS))
+            );
+            assert_eq!(
+                Pair::adjusted_balance(u128::MAX, u128::MAX),
+                Ok(U256::from(u128::MAX) * U256::from(BPS - SWAP_FEE_BPS))
+            );
+        }
+
+        #[ink::test]
+        fn mul_div_keeps_full_precision() {
+            assert_eq!(
+                Pair::mul_div(u128::MAX, u128::MAX, u128::MAX),
+                Ok(u128::MAX)
+            );
+            assert_eq!(Pair::mul_div(u128::MAX, 2, 1), Err(Error::Overflow));
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use erc20::{Erc20, Erc20Ref};
+        use ink_e2e::ContractsBackend;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        #[ink_e2e::test]
+        async fn liquidity_and_swaps<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+            let alice = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let mut token_0_constructor = Erc20Ref::new(1_000_000);
+            let token_0 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut token_0_constructor)
+                .submit()
+                .await
+                .expect("token 0 instantiate failed");
+            let mut token_0_call = token_0.call_builder::<Erc20>();
+            let mut token_1_constructor = Erc20Ref::new(1_000_000);
+            let token_1 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut token_1_constructor)
+                .submit()
+                .await
+                .expect("token 1 instantiate failed");
+            let mut token_1_call = token_1.call_builder::<Erc20>();
+
+            let mut pair_constructor = PairRef::new(token_0.account_id, token_1.account_id);
+            let pair = client
+                .instantiate("amm", &ink_e2e::alice(), &mut pair_constructor)
+                .submit()
+                .await
+                .expect("pair instantiate failed");
+            let mut pair_call = pair.call_builder::<Pair>();
+
+            for token_call in [&mut token_0_call, &mut token_1_call] {
+                client
+                    .call(
+                        &ink_e2e::alice(),
+                        &token_call.approve(pair.account_id, u128::MAX),
+                    )
+                    .submit()
+                    .await
+                    .expect("approve failed");
+            }
+            let liquidity = client
+                .call(
+                    &ink_e2e::alice(),
+                    &pair_call.add_liquidity(100_000, 100_000, 0),
+                )
+                .submit()
+                .await
+                .expect("add_liquidity failed")
+                .return_value()
+                .expect("add_liquidity returned an error");
+            assert_eq!(liquidity, 100_000 - MINIMUM_LIQUIDITY);
+
+            let quote = client
+                .call(
+                    &ink_e2e::alice(),
+                    &pair_call.get_amount_out(token_0.account_id, 1_000),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(quote.return_value(), Ok(987));
+            let slippage = client
+                .call(
+                    &ink_e2e::alice(),
+                    &pair_call.swap(token_0.account_id, 1_000, 988, alice),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(
+                slippage.return_value(),
+                Err(Error::SlippageExceeded {
+                    amount_out: 987,
+                    min_amount_out: 988,
+                })
+            );
+            let amount_out = client
+                .call(
+                    &ink_e2e::alice(),
+                    &pair_call.swap(token_0.account_id, 1_000, 987, alice),
+                )
+                .submit()
+                .await
+                .expect("swap failed")
+                .return_value()
+                .expect("swap returned an error");
+            assert_eq!(amount_out, 987);
+
+            let reserves = client
+                .call(&ink_e2e::alice(), &pair_call.get_reserves())
+                .dry_run()
+                .await?;
+            assert_eq!(reserves.return_value(), (101_000, 99_013));
+
+            let (amount_0, amount_1) = client
+                .call(
+                    &ink_e2e::alice(),
+                    &pair_call.remove_liquidity(liquidity, 0, 0),
+                )
+                .submit()
+                .await
+                .expect("remove_liquidity failed")
+                .return_value()
+                .expect("remove_liquidity returned an error");
+            assert_eq!(amount_0, 101_000 * liquidity / 100_000);
+            assert_eq!(amount_1, 99_013 * liquidity / 100_000);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn flash_swap_enforces_the_fee<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let alice = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let mut token_0_constructor = Erc20Ref::new(1_000_000);
+            let token_0 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut token_0_constructor)
+                .submit()
+                .await
+                .expect("token 0 instantiate failed");
+            let mut token_0_call = token_0.call_builder::<Erc20>();
+            let mut token_1_constructor = Erc20Ref::new(1_000_000);
+            let token_1 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut token_1_constructor)
+                .submit()
+                .await
+                .expect("token 1 instantiate failed");
+            let mut token_1_call = token_1.call_builder::<Erc20>();
+
+            let mut pair_constructor = PairRef::new(token_0.account_id, token_1.account_id);
+            let pair = client
+                .instantiate("amm", &ink_e2e::alice(), &mut pair_constructor)
+                .submit()
+                .await
+                .expect("pair instantiate failed");
+            let mut pair_call = pair.call_builder::<Pair>();
+
+            for token_call in [&mut token_0_call, &mut token_1_call] {
+                client
+                    .call(
+                        &ink_e2e::alice(),
+                        &token_call.approve(pair.account_id, u128::MAX),
+                    )
+                    .submit()
+                    .await
+                    .expect("approve failed");
+            }
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &pair_call.add_liquidity(100_000, 100_000, 0),
+                )
+                .submit()
+                .await
+                .expect("add_liquidity failed");
+
+            // Pay the pair first, then take out the output without a callback.
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_0_call.transfer(pair.account_id, 1_000),
+                )
+                .submit()
+                .await
+                .expect("payment failed");
+            let greedy = client
+                .call(
+                    &ink_e2e::alice(),
+                    &pair_call.flash_swap(0, 988, alice, Vec::new()),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(greedy.return_value(), Err(Error::KInvariant));
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &pair_call.flash_swap(0, 987, alice, Vec::new()),
+                )
+                .submit()
+                .await
+                .expect("flash_swap failed");
+
+            let reserves = client
+                .call(&ink_e2e::alice(), &pair_call.get_reserves())
+                .dry_run()
+                .await?;
+            assert_eq!(reserves.return_value(), (101_000, 99_013));
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,288 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::erc20::{Erc20, Erc20Ref};
+
+#[ink::contract]
+mod erc20 {
+    use ierc20::{Erc20Core, Result, IERC20};
+
+    #[ink(storage)]
+    pub struct Erc20 {
+        /// Balances, allowances and supply of the token.
+        token: Erc20Core,
+    }
+
+    impl IERC20 for Erc20 {
+        /// Returns the total token supply.
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self.token.total_supply()
+        }
+
+        /// Returns the balance of the given `account`.
+        #[ink(message)]
+        fn balance_of(&self, account: AccountId) -> Balance {
+            self.token.balance_of(account)
+        }
+
+        /// Transfers `value` tokens from the caller's account to `to`.
+        ///
+        /// Returns `true` if the operation succeeded.
+        ///
+        /// Emits a `Transfer` event.
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, value: Balance) -> Result<bool> {
+            self.token.transfer(self.env().caller(), to, value)?;
+            Ok(true)
+        }
+
+        /// Returns the remaining number of tokens that `spender` can spend
+        /// on behalf of `owner` through `transfer_from`.
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.token.allowance(owner, spender)
+        }
+
+        /// Sets `value` as the allowance of `spender` over the caller’s tokens.
+        ///
+        /// Returns `true` if the operation succeeded.
+        ///
+        /// Emits an `Approval` event.
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<bool> {
+            self.token.approve(self.env().caller(), spender, value)?;
+            Ok(true)
+        }
+
+        /// Transfers `value` tokens from `from` to `to` using the allowance mechanism.
+        /// `value` is then deducted from the caller’s allowance.
+        ///
+        /// Returns `true` if the operation succeeded.
+        ///
+        /// Emits a `Transfer` event.
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<bool> {
+            self.token
+                .transfer_from(self.env().caller(), from, to, value)?;
+            Ok(true)
+        }
+    }
+
+    impl Erc20 {
+        /// Creates a new [`Erc20`] minting `total_supply` tokens to the caller.
+        ///
+        /// ## Params:
+        /// - `total_supply`: Amount of tokens minted to the deployer.
+        #[ink(constructor)]
+        pub fn new(total_supply: Balance) -> Self {
+            let mut token = Erc20Core::default();
+            token
+                .mint(Self::env().caller(), total_supply)
+                .expect("the deployer is never the zero account");
+            Self { token }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ierc20::{Error, Transfer};
+
+        fn accounts() -> ink::env::test::DefaultAccounts<Environment> {
+            ink::env::test::default_accounts::<Environment>()
+        }
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<Environment>(caller);
+        }
+
+        fn recorded_transfers() -> Vec<Transfer> {
+            let signature = <Transfer as ink::env::Event>::SIGNATURE_TOPIC
+                .expect("`Transfer` is not anonymous");
+            ink::env::test::recorded_events()
+                .filter(|event| event.topics.first().map(|t| &t[..]) == Some(&signature[..]))
+                .map(|event| {
+                    <Transfer as ink::scale::Decode>::decode(&mut &event.data[..])
+                        .expect("a `Transfer` event")
+                })
+                .collect()
+        }
+
+        #[ink::test]
+        fn new_mints_total_supply_to_deployer() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let erc20 = Erc20::new(1_000);
+            assert_eq!(erc20.total_supply(), 1_000);
+            assert_eq!(erc20.balance_of(accounts.alice), 1_000);
+            assert_eq!(erc20.balance_of(accounts.bob), 0);
+            let transfers = recorded_transfers();
+            assert_eq!(transfers.len(), 1);
+            assert_eq!(transfers[0].from, Erc20Core::zero_account());
+            assert_eq!(transfers[0].to, accounts.alice);
+            assert_eq!(transfers[0].value, 1_000);
+        }
+
+        #[ink::test]
+        fn transfer_moves_balance() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut erc20 = Erc20::new(1_000);
+            assert_eq!(erc20.transfer(accounts.bob, 300), Ok(true));
+            assert_eq!(erc20.balance_of(accounts.alice), 700);
+            assert_eq!(erc20.balance_of(accounts.bob), 300);
+            assert_eq!(erc20.total_supply(), 1_000);
+            assert_eq!(ink::env::test::recorded_events().count(), 2);
+        }
+
+        #[ink::test]
+        fn transfer_fails_on_insufficient_balance() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(
+                erc20.transfer(accounts.bob, 101),
+                Err(Error::InsufficientBalance {
+                    sender: accounts.alice,
+                    balance: 100,
+                    needed: 101,
+                })
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+        }
+
+        #[ink::test]
+        fn transfer_to_zero_account_fails() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(
+                erc20.transfer(Erc20Core::zero_account(), 1),
+                Err(Error::InvalidReceiver {
+                    receiver: Erc20Core::zero_account(),
+                })
+            );
+        }
+
+        #[ink::test]
+        fn transfer_from_zero_account_fails() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut erc20 = Erc20::new(100);
+            set_caller(Erc20Core::zero_account());
+            assert_eq!(
+                erc20.transfer(accounts.bob, 0),
+                Err(Error::InvalidSender {
+                    sender: Erc20Core::zero_account(),
+                })
+            );
+        }
+
+        #[ink::test]
+        fn approve_sets_allowance() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(erc20.approve(accounts.bob, 40), Ok(true));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 40);
+            assert_eq!(erc20.approve(accounts.bob, 10), Ok(true));
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 10);
+        }
+
+        #[ink::test]
+        fn approve_zero_spender_fails() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut erc20 = Erc20::new(100);
+            assert_eq!(
+                erc20.approve(Erc20Core::zero_account(), 1),
+                Err(Error::InvalidSpender {
+                    spender: Erc20Core::zero_account(),
+                })
+            );
+        }
+
+        #[ink::test]
+        fn approve_from_zero_account_fails() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut erc20 = Erc20::new(100);
+            set_caller(Erc20Core::zero_account());
+            assert_eq!(
+                erc20.approve(accounts.bob, 1),
+                Err(Error::InvalidApprover {
+                    approver: Erc20Core::zero_account(),
+                })
+            );
+        }
+
+        #[ink::test]
+        fn transfer_from_spends_allowance() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut erc20 = Erc20::new(100);
+            erc20.approve(accounts.bob, 60).unwrap();
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.charlie, 50),
+                Ok(true)
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 50);
+            assert_eq!(erc20.balance_of(accounts.charlie), 50);
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), 10);
+        }
+
+        #[ink::test]
+        fn transfer_from_fails_on_insufficient_allowance() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut erc20 = Erc20::new(100);
+            erc20.approve(accounts.bob, 10).unwrap();
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.charlie, 11),
+                Err(Error::InsufficientAllowance {
+                    spender: accounts.bob,
+                    allowance: 10,
+                    needed: 11,
+                })
+            );
+            assert_eq!(erc20.balance_of(accounts.alice), 100);
+        }
+
+        #[ink::test]
+        fn transfer_from_keeps_infinite_allowance() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut erc20 = Erc20::new(100);
+            erc20.approve(accounts.bob, Balance::MAX).unwrap();
+            set_caller(accounts.bob);
+            erc20
+                .transfer_from(accounts.alice, accounts.charlie, 100)
+                .unwrap();
+            assert_eq!(erc20.allowance(accounts.alice, accounts.bob), Balance::MAX);
+        }
+
+        #[ink::test]
+        fn transfer_from_fails_on_insufficient_balance() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut erc20 = Erc20::new(100);
+            erc20.approve(accounts.bob, 200).unwrap();
+            set_caller(accounts.bob);
+            assert_eq!(
+                erc20.transfer_from(accounts.alice, accounts.charlie, 150),
+                Err(Error::InsufficientBalance {
+                    sender: accounts.alice,
+                    balance: 100,
+                    needed: 150,
+                })
+            );
+        }
+    }
+}
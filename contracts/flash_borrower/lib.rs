@@ -1,28 +1,240 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
+pub use self::flash_receiver::{
+    Action, ActionPayload, FlashBorrower, FlashBorrowerRef, PendingBatchLoan, PendingLoan,
+    StrategyTarget,
+};
+
 #[ink::contract]
 mod flash_receiver {
+    use iamm::IPair;
     use ierc20::IERC20;
+    use ierc3156::ierc3156_batch_flash_borrower::IERC3156BatchFlashBorrower;
+    use ierc3156::ierc3156_batch_flash_lender::IERC3156BatchFlashLender;
     use ierc3156::ierc3156_flash_borrower::{Error, IERC3156FlashBorrower, Result};
     use ierc3156::ierc3156_flash_lender::IERC3156FlashLender;
-    use ink::env::hash::Keccak256;
+    use ierc3156::ierc3156_flash_strategy::IFlashLoanStrategy;
+    use ilending::ILendingMarket;
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::env::{hash::Keccak256, CallFlags};
+    use ink::prelude::vec;
     use ink::prelude::vec::Vec;
     use ink::scale::{Decode, Encode};
+    use ink::storage::{Lazy, Mapping};
+
+    /// Where a strategy's logic lives.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub enum StrategyTarget {
+        /// A deployed `IFlashLoanStrategy` contract, called with the borrowed funds.
+        Contract(AccountId),
+        /// `IFlashLoanStrategy` code run through a delegate call in the borrower context.
+        Code(Hash),
+    }
 
-    #[derive(Debug, PartialEq, Eq)]
+    /// What the borrower does with the funds during the loan callback.
+    ///
+    /// Variant indexes are fixed so that encoded payloads keep their meaning. Index 1
+    /// belonged to a removed placeholder and is not reused.
+    #[derive(Debug, Clone, PartialEq, Eq)]
     #[ink::scale_derive(Encode, Decode, TypeInfo)]
     #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
     pub enum Action {
+        #[codec(index = 0)]
         Normal,
-        Other,
+        /// Runs a registered strategy with the given parameters.
+        #[codec(index = 2)]
+        Strategy {
+            target: StrategyTarget,
+            params: Vec<u8>,
+        },
+        /// Sells the borrowed token for another one on `first_pool`, and buys it
+        /// back on `second_pool`, keeping the spread.
+        #[codec(index = 3)]
+        Arbitrage {
+            /// The pair the borrowed token is sold on.
+            first_pool: AccountId,
+            /// The pair the intermediate token is sold on, for the borrowed token.
+            second_pool: AccountId,
+            /// The minimum amount of the intermediate token bought on `first_pool`.
+            min_intermediate_out: Balance,
+            /// The minimum amount of the borrowed token bought back on `second_pool`.
+            min_amount_out: Balance,
+        },
+        /// Repays the borrowed debt token for an unhealthy `borrower` on `market`,
+        /// and sells the seized collateral for the debt token on `collateral_pool`.
+        #[codec(index = 4)]
+        Liquidation {
+            /// The lending market holding the position.
+            market: AccountId,
+            /// The owner of the unhealthy position.
+            borrower: AccountId,
+            /// The pair the seized collateral is sold on.
+            collateral_pool: AccountId,
+            /// The minimum amount of the debt token bought with the collateral.
+            min_amount_out: Balance,
+        },
+        /// Repays the borrowed debt token on the borrower's own position on `market`,
+        /// sells its whole collateral for `new_collateral` on `collateral_pool`, and
+        /// deposits it back before borrowing the repayment of the loan.
+        #[codec(index = 5)]
+        CollateralSwap {
+            /// The lending market holding the borrower's position.
+            market: AccountId,
+            /// The token replacing the current collateral.
+            new_collateral: AccountId,
+            /// The pair the current collateral is sold on.
+            collateral_pool: AccountId,
+            /// The minimum amount of `new_collateral` bought.
+            min_amount_out: Balance,
+        },
+        /// Repays the borrowed debt token on the borrower's own position on
+        /// `from_market`, moves the collateral to `to_market`, and borrows the
+        /// repayment of the loan there.
+        #[codec(index = 6)]
+        Refinance {
+            /// The lending market the position leaves.
+            from_market: AccountId,
+            /// The lending market the position moves to.
+            to_market: AccountId,
+        },
+    }
+
+    /// The versioned format of the loan `data` understood by the borrower.
+    ///
+    /// Variant indexes are fixed so that payloads encoded by off-chain tooling keep
+    /// decoding when new versions are added.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum ActionPayload {
+        #[codec(index = 1)]
+        V1(Action),
+    }
+
+    impl From<ActionPayload> for Action {
+        fn from(payload: ActionPayload) -> Self {
+            match payload {
+                ActionPayload::V1(action) => action,
+            }
+        }
+    }
+
+    /// The loan the borrower is waiting the lender to call back for.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct PendingLoan {
+        pub lender: AccountId,
+        pub token: AccountId,
+        pub amount: Balance,
+        pub fee: Balance,
+        /// Keccak-256 hash of the data sent with the loan.
+        pub data_hash: [u8; 32],
+    }
+
+    /// The batch loan the borrower is waiting the lender to call back for.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct PendingBatchLoan {
+        pub lender: AccountId,
+        pub tokens: Vec<AccountId>,
+        pub amounts: Vec<Balance>,
+        pub fees: Vec<Balance>,
+        /// Keccak-256 hash of the data sent with the loan.
+        pub data_hash: [u8; 32],
     }
 
     #[ink(storage)]
     pub struct FlashBorrower {
-        /// Stores the receiver lender's AccountId.
+        /// The lender used by `flash_borrow` and `batch_flash_borrow`.
         lender: AccountId,
+        /// Lenders the borrower accepts loans and callbacks from.
+        lenders: Mapping<AccountId, ()>,
         /// Stores the last action performed.
         action: Action,
+        /// Account allowed to manage the borrower configuration.
+        owner: AccountId,
+        /// Strategy contracts registered by the owner.
+        strategies: Mapping<AccountId, ()>,
+        /// Strategy code hashes registered by the owner.
+        strategy_codes: Mapping<Hash, ()>,
+        /// Accounts approved by the owner to request loans.
+        operators: Mapping<AccountId, ()>,
+        /// The loan in flight, stored outside the root cell so the callback sees it.
+        pending_loan: Lazy<Option<PendingLoan>>,
+        /// The batch loan in flight, stored like `pending_loan`.
+        pending_batch_loan: Lazy<Option<PendingBatchLoan>>,
+    }
+
+    /// Emitted when the owner trusts a lender.
+    #[ink(event)]
+    pub struct LenderAdded {
+        #[ink(topic)]
+        lender: AccountId,
+    }
+
+    /// Emitted when the owner stops trusting a lender.
+    #[ink(event)]
+    pub struct LenderRemoved {
+        #[ink(topic)]
+        lender: AccountId,
+    }
+
+    /// Emitted when the owner approves or revokes an operator.
+    #[ink(event)]
+    pub struct OperatorUpdated {
+        #[ink(topic)]
+        operator: AccountId,
+        approved: bool,
+    }
+
+    /// Emitted when the owner moves tokens out of the borrower.
+    #[ink(event)]
+    pub struct Withdrawal {
+        #[ink(topic)]
+        token: AccountId,
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+    }
+
+    /// Emitted when the owner registers a strategy.
+    #[ink(event)]
+    pub struct StrategyRegistered {
+        #[ink(topic)]
+        target: StrategyTarget,
+    }
+
+    /// Emitted when the owner unregisters a strategy.
+    #[ink(event)]
+    pub struct StrategyUnregistered {
+        #[ink(topic)]
+        target: StrategyTarget,
+    }
+
+    /// Emitted when the borrower requests a flash loan.
+    #[ink(event)]
+    pub struct LoanInitiated {
+        #[ink(topic)]
+        lender: AccountId,
+        #[ink(topic)]
+        token: AccountId,
+        amount: Balance,
+        fee: Balance,
+    }
+
+    /// Emitted when the borrower has executed its logic with the borrowed funds.
+    #[ink(event)]
+    pub struct LoanExecuted {
+        #[ink(topic)]
+        lender: AccountId,
+        #[ink(topic)]
+        token: AccountId,
+        amount: Balance,
+        fee: Balance,
     }
 
     impl IERC3156FlashBorrower for FlashBorrower {
@@ -50,26 +262,18 @@ mod flash_receiver {
             fee: Balance,
             data: Vec<u8>,
         ) -> Result<[u8; 32]> {
-            let caller = self.env().caller();
-            if caller != self.lender {
-                return Err(Error::UntrustedLender);
-            }
-            if initiator != self.env().account_id() {
-                return Err(Error::UntrustedLoanInitiator);
-            }
+            self.ensure_trusted_callback(initiator)?;
+            self.ensure_pending_loan(token, amount, fee, &data)?;
 
             let decoded_action = self.decode_action(data)?;
+            self.execute_action(decoded_action, vec![token], vec![amount], vec![fee])?;
+            self.env().emit_event(LoanExecuted {
+                lender: self.env().caller(),
+                token,
+                amount,
+                fee,
+            });
 
-            match decoded_action {
-                Action::Normal => {
-                    // Mock an arbitrage action, this should be an EV+ operation
-                    // TODO: Profitable logic would go here
-                    // Emit event
-                }
-                Action::Other => {
-                    // Perform other action
-                }
-            }
             Ok(self
                 .env()
                 .hash_bytes::<Keccak256>(b"ERC3156FlashBorrower.onFlashLoan"))
@@ -77,32 +281,184 @@ mod flash_receiver {
 
         /// Initiates a flash loan from the trusted lender.
         ///
-        /// Prepares the encoded action data, checks and increases allowance if necessary,
-        /// and requests a flash loan from the lender.
+        /// Prepares the encoded action data, approves the lender for exactly the repayment,
+        /// and requests a flash loan from the lender. The allowance is reset to zero once
+        /// the loan is over.
         ///
         /// ## Parameters:
         /// - `token`: The address of the token to borrow.
         /// - `amount`: The amount of tokens to borrow.
+        /// - `data`: An encoded [`ActionPayload`]. Empty data runs `Action::Normal`.
+        /// - `min_profit`: The minimum gain in `token` the loan must produce, or `None` to
+        ///   accept any loss.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is neither the owner nor an operator.
+        /// - `ScaleDecodingErr` if `data` is not a valid payload.
+        /// - `InsufficientProfit` if the loan gained less than `min_profit`.
+        /// - `Overflow` if the repayment quoted by the lender overflows.
         #[ink(message)]
-        fn flash_borrow(&self, token: AccountId, amount: u128) -> Result<()> {
-            let mut erc20: ink::contract_ref!(IERC20) = token.into();
-            let lender: ink::contract_ref!(IERC3156FlashLender) = self.lender.into();
-            let allowance = erc20.allowance(self.env().account_id(), self.lender);
-            let fee = lender
-                .flash_fee(token, amount)
-                .map_err(|e| Error::ERC3156LenderError(e))?;
-            let repayment = amount + fee;
-            erc20
-                .approve(self.lender, allowance + repayment)
-                .map_err(|e| Error::ERC20Error(e))?;
-            lender
-                .flash_loan(
-                    self.env().account_id(),
+        fn flash_borrow(
+            &mut self,
+            token: AccountId,
+            amount: u128,
+            data: Vec<u8>,
+            min_profit: Option<u128>,
+        ) -> Result<()> {
+            self.ensure_owner_or_operator()?;
+            let action = self.decode_action(data)?;
+            self._flash_borrow(self.lender, token, amount, min_profit, action)
+        }
+    }
+
+    impl IERC3156BatchFlashBorrower for FlashBorrower {
+        /// Batch flash loan callback.
+        ///
+        /// This function is called by the lender after the tokens of every leg have
+        /// been transferred. It verifies the caller, the initiator and that the loan
+        /// matches the one requested by `batch_flash_borrow`, decodes the action,
+        /// and executes custom logic depending on the action type.
+        ///
+        /// ## Parameters:
+        /// - `initiator`: The account that initiated the loan. Must be `self`.
+        /// - `tokens`: The addresses of the tokens that were lent.
+        /// - `amounts`: The amount borrowed of each token.
+        /// - `fees`: The fee charged by the lender for each token.
+        /// - `data`: Encoded arbitrary data, usually used to signal the type of action.
+        ///
+        /// ## Returns:
+        /// - A `bool` hash signaling successful execution of the callback.
+        #[ink(message)]
+        fn on_batch_flash_loan(
+            &self,
+            initiator: AccountId,
+            tokens: Vec<AccountId>,
+            amounts: Vec<Balance>,
+            fees: Vec<Balance>,
+            data: Vec<u8>,
+        ) -> Result<[u8; 32]> {
+            self.ensure_trusted_callback(initiator)?;
+            self.ensure_pending_batch_loan(&tokens, &amounts, &fees, &data)?;
+
+            let decoded_action = self.decode_action(data)?;
+            self.execute_action(
+                decoded_action,
+                tokens.clone(),
+                amounts.clone(),
+                fees.clone(),
+            )?;
+            for ((token, amount), fee) in tokens.into_iter().zip(amounts).zip(fees) {
+                self.env().emit_event(LoanExecuted {
+                    lender: self.env().caller(),
                     token,
                     amount,
-                    self.encode_action(Action::Normal),
-                )
-                .map_err(|e| Error::ERC3156LenderError(e))?;
+                    fee,
+                });
+            }
+
+            Ok(self
+                .env()
+                .hash_bytes::<Keccak256>(b"ERC3156BatchFlashBorrower.onBatchFlashLoan"))
+        }
+
+        /// Initiates a batch flash loan from the trusted lender.
+        ///
+        /// Prepares the encoded action data, approves the lender for exactly the repayment
+        /// of every token, and requests a batch flash loan from the lender. The allowances
+        /// are reset to zero once the loan is over.
+        ///
+        /// The loan is recorded as pending while the lender runs, so that the callback
+        /// only accepts the exact legs, quoted fees and data. Once every leg is repaid,
+        /// the balance of each token must have grown by at least the sum of
+        /// `min_profits` of its legs. A `min_profits` of `None` skips the check, so the
+        /// fees and any loss of the action are paid from reserves.
+        ///
+        /// ## Parameters:
+        /// - `tokens`: The addresses of the tokens to borrow.
+        /// - `amounts`: The amount of each token to borrow.
+        /// - `data`: An encoded [`ActionPayload`]. Empty data runs `Action::Normal`.
+        /// - `min_profits`: The minimum gain each leg must produce in its token, or
+        ///   `None` to accept any loss.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is neither the owner nor an operator.
+        /// - `InconsistentBatchLength` if `min_profits` does not match `tokens`.
+        /// - `ScaleDecodingErr` if `data` is not a valid payload.
+        /// - `InsufficientProfit` if a token gained less than its minimum profit.
+        /// - `Overflow` if the repayment quoted by the lender overflows.
+        #[ink(message)]
+        fn batch_flash_borrow(
+            &mut self,
+            tokens: Vec<AccountId>,
+            amounts: Vec<u128>,
+            data: Vec<u8>,
+            min_profits: Option<Vec<u128>>,
+        ) -> Result<()> {
+            self.ensure_owner_or_operator()?;
+            let guarded = min_profits.is_some();
+            let min_profits = min_profits.unwrap_or_else(|| vec![0; tokens.len()]);
+            if min_profits.len() != tokens.len() {
+                return Err(Error::InconsistentBatchLength);
+            }
+            let action = self.decode_action(data)?;
+            let mut lender: ink::contract_ref!(IERC3156BatchFlashLender) = self.lender.into();
+            let fees = lender
+                .batch_flash_fee(tokens.clone(), amounts.clone())
+                .map_err(Error::ERC3156LenderError)?;
+            // A token may back several legs, so its allowance and minimum profit
+            // cover all of them.
+            let mut repayments: Vec<(AccountId, Balance, Balance, Balance)> = Vec::new();
+            for (((token, amount), fee), min_profit) in
+                tokens.iter().zip(&amounts).zip(&fees).zip(&min_profits)
+            {
+                let repayment = amount.checked_add(*fee).ok_or(Error::Overflow)?;
+                match repayments.iter_mut().find(|(t, ..)| t == token) {
+                    Some((_, total, _, total_min_profit)) => {
+                        *total = total.checked_add(repayment).ok_or(Error::Overflow)?;
+                        *total_min_profit = total_min_profit.saturating_add(*min_profit);
+                    }
+                    None => {
+                        let erc20: ink::contract_ref!(IERC20) = (*token).into();
+                        let balance_before = erc20.balance_of(self.env().account_id());
+                        repayments.push((*token, repayment, balance_before, *min_profit));
+                    }
+                }
+                self.env().emit_event(LoanInitiated {
+                    lender: self.lender,
+                    token: *token,
+                    amount: *amount,
+                    fee: *fee,
+                });
+            }
+            for (token, repayment, ..) in &repayments {
+                self._approve_lender(self.lender, *token, *repayment)?;
+            }
+            let data = self.encode_action(action.clone());
+            self.pending_batch_loan.set(&Some(PendingBatchLoan {
+                lender: self.lender,
+                tokens: tokens.clone(),
+                amounts: amounts.clone(),
+                fees,
+                data_hash: self.env().hash_bytes::<Keccak256>(&data),
+            }));
+            // The lender calls back into `on_batch_flash_loan`, so reentry must be allowed.
+            lender
+                .call_mut()
+                .batch_flash_loan(self.env().account_id(), tokens, amounts, data)
+                .call_flags(CallFlags::ALLOW_REENTRY)
+                .invoke()
+                .map_err(Error::ERC3156LenderError)?;
+            self.pending_batch_loan.set(&None);
+            for (token, _, balance_before, min_profit) in repayments {
+                self._approve_lender(self.lender, token, 0)?;
+                let erc20: ink::contract_ref!(IERC20) = token.into();
+                let balance = erc20.balance_of(self.env().account_id());
+                let needed = balance_before.saturating_add(min_profit);
+                if guarded && balance < needed {
+                    return Err(Error::InsufficientProfit { balance, needed });
+                }
+            }
+            self.action = action;
             Ok(())
         }
     }
@@ -111,23 +467,2602 @@ mod flash_receiver {
         /// Creates a new [`FlashBorrower`] instance.
         ///
         /// ## Parameters:
-        /// - `lender_`: The trusted flash lender contract.
+        /// - `lender`: The default flash lender contract, trusted from the start.
         #[ink(constructor)]
         pub fn new(lender: AccountId) -> Self {
+            let mut lenders = Mapping::default();
+            lenders.insert(lender, &());
             Self {
                 lender,
+                lenders,
                 action: Action::Normal,
+                owner: Self::env().caller(),
+                strategies: Mapping::default(),
+                strategy_codes: Mapping::default(),
+                operators: Mapping::default(),
+                pending_loan: Lazy::default(),
+                pending_batch_loan: Lazy::default(),
             }
         }
 
-        /// Decodes the data into an action
-        fn decode_action(&self, data: Vec<u8>) -> Result<Action> {
-            Action::decode(&mut &data[..]).map_err(|_| Error::ScaleDecodingErr)
+        /// Returns the current owner.
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
         }
 
-        /// Encodes action into data
-        fn encode_action(&self, action: Action) -> Vec<u8> {
-            Action::encode(&action)
+        /// Returns the action run by the last completed loan.
+        #[ink(message)]
+        pub fn last_action(&self) -> Action {
+            self.action.clone()
+        }
+
+        /// Returns the lender used by `flash_borrow` and `batch_flash_borrow`.
+        #[ink(message)]
+        pub fn default_lender(&self) -> AccountId {
+            self.lender
+        }
+
+        /// Returns whether `lender` is a trusted lender.
+        #[ink(message)]
+        pub fn is_trusted_lender(&self, lender: AccountId) -> bool {
+            self.lenders.contains(lender)
+        }
+
+        /// Adds `lender` to the trusted lenders.
+        ///
+        /// ## Parameters:
+        /// - `lender`: The flash lender contract to trust.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        #[ink(message)]
+        pub fn add_trusted_lender(&mut self, lender: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.lenders.insert(lender, &());
+            self.env().emit_event(LenderAdded { lender });
+            Ok(())
+        }
+
+        /// Removes `lender` from the trusted lenders.
+        ///
+        /// Removing the default lender makes `flash_borrow` fail until it is trusted again.
+        ///
+        /// ## Parameters:
+        /// - `lender`: The flash lender contract to stop trusting.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        #[ink(message)]
+        pub fn remove_trusted_lender(&mut self, lender: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.lenders.remove(lender);
+            self.env().emit_event(LenderRemoved { lender });
+            Ok(())
+        }
+
+        /// Initiates a flash loan from the trusted `lender`.
+        ///
+        /// Behaves like `flash_borrow`, for callers routing loans to the lender with
+        /// the most liquidity or the lowest fee.
+        ///
+        /// ## Parameters:
+        /// - `lender`: The trusted lender to borrow from.
+        /// - `token`: The address of the token to borrow.
+        /// - `amount`: The amount of tokens to borrow.
+        /// - `data`: An encoded [`ActionPayload`]. Empty data runs `Action::Normal`.
+        /// - `min_profit`: The minimum gain in `token` the loan must produce, or `None` to
+        ///   accept any loss.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is neither the owner nor an operator.
+        /// - `ScaleDecodingErr` if `data` is not a valid payload.
+        /// - `UntrustedLender` if `lender` is not trusted.
+        /// - `InsufficientProfit` if the loan gained less than `min_profit`.
+        /// - `Overflow` if the repayment quoted by the lender overflows.
+        #[ink(message)]
+        pub fn flash_borrow_from(
+            &mut self,
+            lender: AccountId,
+            token: AccountId,
+            amount: u128,
+            data: Vec<u8>,
+            min_profit: Option<u128>,
+        ) -> Result<()> {
+            self.ensure_owner_or_operator()?;
+            let action = self.decode_action(data)?;
+            self._flash_borrow(lender, token, amount, min_profit, action)
+        }
+
+        /// Initiates a flash loan from the trusted `lender` that runs `action`.
+        ///
+        /// ## Parameters:
+        /// - `lender`: The trusted lender to borrow from.
+        /// - `token`: The address of the token to borrow.
+        /// - `amount`: The amount of tokens to borrow.
+        /// - `action`: The action run in the callback.
+        /// - `min_profit`: The minimum gain in `token` the loan must produce, or `None` to
+        ///   accept any loss.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is neither the owner nor an operator.
+        /// - `UntrustedLender` if `lender` is not trusted.
+        /// - `InsufficientProfit` if the loan gained less than `min_profit`.
+        /// - `Overflow` if the repayment quoted by the lender overflows.
+        #[ink(message)]
+        pub fn flash_borrow_with_action(
+            &mut self,
+            lender: AccountId,
+            token: AccountId,
+            amount: u128,
+            action: Action,
+            min_profit: Option<u128>,
+        ) -> Result<()> {
+            self.ensure_owner_or_operator()?;
+            self._flash_borrow(lender, token, amount, min_profit, action)
+        }
+
+        /// Returns whether `account` is an approved operator.
+        #[ink(message)]
+        pub fn is_operator(&self, account: AccountId) -> bool {
+            self.operators.contains(account)
+        }
+
+        /// Approves or revokes `operator` to request loans on behalf of the owner.
+        ///
+        /// ## Parameters:
+        /// - `operator`: The account to update.
+        /// - `approved`: Whether `operator` may request loans.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        #[ink(message)]
+        pub fn set_operator(&mut self, operator: AccountId, approved: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if approved {
+                self.operators.insert(operator, &());
+            } else {
+                self.operators.remove(operator);
+            }
+            self.env()
+                .emit_event(OperatorUpdated { operator, approved });
+            Ok(())
+        }
+
+        /// Transfers `amount` of `token` held by the borrower to `to`.
+        ///
+        /// ## Parameters:
+        /// - `token`: The token to withdraw.
+        /// - `amount`: The amount to withdraw.
+        /// - `to`: The recipient of the tokens.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        /// - `ERC20Error` if the transfer fails.
+        #[ink(message)]
+        pub fn withdraw(&mut self, token: AccountId, amount: Balance, to: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self._withdraw(token, amount, to)
+        }
+
+        /// Transfers the whole `token` balance of the borrower to the owner.
+        ///
+        /// ## Parameters:
+        /// - `token`: The token to sweep.
+        ///
+        /// ## Returns:
+        /// - The amount of tokens swept.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        /// - `ERC20Error` if the transfer fails.
+        #[ink(message)]
+        pub fn sweep(&mut self, token: AccountId) -> Result<Balance> {
+            self.ensure_owner()?;
+            let erc20: ink::contract_ref!(IERC20) = token.into();
+            let amount = erc20.balance_of(self.env().account_id());
+            self._withdraw(token, amount, self.owner)?;
+            Ok(amount)
+        }
+
+        /// Deposits `collateral_amount` of `collateral` held by the borrower on `market`
+        /// and borrows `amount` of `token` against it.
+        ///
+        /// The position belongs to the borrower, which is what `Action::CollateralSwap`
+        /// and `Action::Refinance` manage. The borrowed tokens stay in the borrower.
+        ///
+        /// ## Parameters:
+        /// - `market`: The lending market to open the position on.
+        /// - `collateral`: The collateral token.
+        /// - `collateral_amount`: The amount of `collateral` deposited.
+        /// - `token`: The token to borrow.
+        /// - `amount`: The amount of `token` to borrow.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        /// - `ActionFailed` if the market rejects the deposit or the borrow.
+        #[ink(message)]
+        pub fn open_position(
+            &mut self,
+            market: AccountId,
+            collateral: AccountId,
+            collateral_amount: Balance,
+            token: AccountId,
+            amount: Balance,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            self._open_position(market, collateral, collateral_amount, token, amount)
+        }
+
+        /// Repays `amount` of the `token` debt of the borrower's position on `market`
+        /// from the borrower's funds and withdraws the whole collateral to the borrower.
+        ///
+        /// ## Parameters:
+        /// - `market`: The lending market holding the position.
+        /// - `token`: The debt token.
+        /// - `amount`: The amount of debt to repay, which must be all of it.
+        ///
+        /// ## Returns:
+        /// - The collateral token and the amount withdrawn.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        /// - `InvalidAction` if the borrower has no collateral on `market`.
+        /// - `ActionFailed` if the market rejects the repayment or the withdrawal.
+        #[ink(message)]
+        pub fn close_position(
+            &mut self,
+            market: AccountId,
+            token: AccountId,
+            amount: Balance,
+        ) -> Result<(AccountId, Balance)> {
+            self.ensure_owner()?;
+            self._close_position(market, token, amount)
+        }
+
+        /// Returns whether `target` is a registered strategy.
+        #[ink(message)]
+        pub fn is_strategy_registered(&self, target: StrategyTarget) -> bool {
+            match target {
+                StrategyTarget::Contract(strategy) => self.strategies.contains(strategy),
+                StrategyTarget::Code(code_hash) => self.strategy_codes.contains(code_hash),
+            }
+        }
+
+        /// Registers `target` as a strategy that loans can run.
+        ///
+        /// ## Parameters:
+        /// - `target`: The strategy contract or code hash.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        #[ink(message)]
+        pub fn register_strategy(&mut self, target: StrategyTarget) -> Result<()> {
+            self.ensure_owner()?;
+            match target {
+                StrategyTarget::Contract(strategy) => self.strategies.insert(strategy, &()),
+                StrategyTarget::Code(code_hash) => self.strategy_codes.insert(code_hash, &()),
+            };
+            self.env().emit_event(StrategyRegistered { target });
+            Ok(())
+        }
+
+        /// Unregisters the strategy `target`.
+        ///
+        /// ## Parameters:
+        /// - `target`: The strategy contract or code hash.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        #[ink(message)]
+        pub fn unregister_strategy(&mut self, target: StrategyTarget) -> Result<()> {
+            self.ensure_owner()?;
+            match target {
+                StrategyTarget::Contract(strategy) => self.strategies.remove(strategy),
+                StrategyTarget::Code(code_hash) => self.strategy_codes.remove(code_hash),
+            }
+            self.env().emit_event(StrategyUnregistered { target });
+            Ok(())
+        }
+
+        /// Initiates a flash loan from the trusted `lender` that runs a registered
+        /// strategy in the callback.
+        ///
+        /// ## Parameters:
+        /// - `lender`: The trusted lender to borrow from.
+        /// - `token`: The address of the token to borrow.
+        /// - `amount`: The amount of tokens to borrow.
+        /// - `target`: The registered strategy to run.
+        /// - `params`: Encoded parameters forwarded to the strategy.
+        /// - `min_profit`: The minimum gain in `token` the loan must produce, or `None` to
+        ///   accept any loss.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is neither the owner nor an operator.
+        /// - `UntrustedLender` if `lender` is not trusted.
+        /// - `UnregisteredStrategy` if `target` is not registered.
+        /// - `InsufficientProfit` if the loan gained less than `min_profit`.
+        /// - `Overflow` if the repayment quoted by the lender overflows.
+        #[ink(message)]
+        pub fn flash_borrow_with_strategy(
+            &mut self,
+            lender: AccountId,
+            token: AccountId,
+            amount: u128,
+            target: StrategyTarget,
+            params: Vec<u8>,
+            min_profit: Option<u128>,
+        ) -> Result<()> {
+            self.ensure_owner_or_operator()?;
+            if !self.is_strategy_registered(target) {
+                return Err(Error::UnregisteredStrategy);
+            }
+            self._flash_borrow(
+                lender,
+                token,
+                amount,
+                min_profit,
+                Action::Strategy { target, params },
+            )
+        }
+
+        /// Requests a flash loan of `amount` of `token` from the trusted `lender`,
+        /// running `action` in the callback and storing it once the loan succeeds.
+        ///
+        /// Approves the lender to pull exactly the repayment and resets the allowance
+        /// to zero after the loan, whether or not the lender used all of it. Once the
+        /// lender has been repaid, the balance of `token` must have grown by at least
+        /// `min_profit`, which is the post-strategy balance minus `amount + fee`. A
+        /// `min_profit` of `None` skips the check, so the fee and any loss of the
+        /// action are paid from reserves.
+        ///
+        /// The loan is recorded as pending while the lender runs, so that the callback
+        /// only accepts the exact `lender`, `token`, `amount`, quoted `fee` and data.
+        fn _flash_borrow(
+            &mut self,
+            lender_account: AccountId,
+            token: AccountId,
+            amount: u128,
+            min_profit: Option<u128>,
+            action: Action,
+        ) -> Result<()> {
+            let erc20: ink::contract_ref!(IERC20) = token.into();
+            if !self.lenders.contains(lender_account) {
+                return Err(Error::UntrustedLender);
+            }
+            let mut lender: ink::contract_ref!(IERC3156FlashLender) = lender_account.into();
+            let balance_before = erc20.balance_of(self.env().account_id());
+            let fee = lender
+                .flash_fee(token, amount)
+                .map_err(Error::ERC3156LenderError)?;
+            let repayment = amount.checked_add(fee).ok_or(Error::Overflow)?;
+            self._approve_lender(lender_account, token, repayment)?;
+            self.env().emit_event(LoanInitiated {
+                lender: lender_account,
+                token,
+                amount,
+                fee,
+            });
+            let data = self.encode_action(action.clone());
+            self.pending_loan.set(&Some(PendingLoan {
+                lender: lender_account,
+                token,
+                amount,
+                fee,
+                data_hash: self.env().hash_bytes::<Keccak256>(&data),
+            }));
+            // The lender calls back into `on_flash_loan`, so reentry must be allowed.
+            lender
+                .call_mut()
+                .flash_loan(self.env().account_id(), token, amount, data)
+                .call_flags(CallFlags::ALLOW_REENTRY)
+                .invoke()
+                .map_err(Error::ERC3156LenderError)?;
+            self.pending_loan.set(&None);
+            self._approve_lender(lender_account, token, 0)?;
+            if let Some(min_profit) = min_profit {
+                let balance = erc20.balance_of(self.env().account_id());
+                let needed = balance_before.saturating_add(min_profit);
+                if balance < needed {
+                    return Err(Error::InsufficientProfit { balance, needed });
+                }
+            }
+            self.action = action;
+            Ok(())
+        }
+
+        /// Sets the allowance of `lender` over `token` to `amount`.
+        fn _approve_lender(
+            &self,
+            lender: AccountId,
+            token: AccountId,
+            amount: Balance,
+        ) -> Result<()> {
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            erc20.approve(lender, amount).map_err(Error::ERC20Error)?;
+            Ok(())
+        }
+
+        /// Returns `Unauthorized` unless the caller is the owner.
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        /// Returns `Unauthorized` unless the caller is the owner or an operator.
+        fn ensure_owner_or_operator(&self) -> Result<()> {
+            let caller = self.env().caller();
+            if caller != self.owner && !self.operators.contains(caller) {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        /// Transfers `amount` of `token` to `to` and emits a [`Withdrawal`].
+        fn _withdraw(&self, token: AccountId, amount: Balance, to: AccountId) -> Result<()> {
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            erc20.transfer(to, amount).map_err(Error::ERC20Error)?;
+            self.env().emit_event(Withdrawal { token, to, amount });
+            Ok(())
+        }
+
+        /// Checks that a loan callback comes from a trusted lender and was
+        /// initiated by this contract.
+        fn ensure_trusted_callback(&self, initiator: AccountId) -> Result<()> {
+            if !self.lenders.contains(self.env().caller()) {
+                return Err(Error::UntrustedLender);
+            }
+            if initiator != self.env().account_id() {
+                return Err(Error::UntrustedLoanInitiator);
+            }
+            Ok(())
+        }
+
+        /// Checks that a loan callback matches the loan requested by `flash_borrow`.
+        fn ensure_pending_loan(
+            &self,
+            token: AccountId,
+            amount: Balance,
+            fee: Balance,
+            data: &[u8],
+        ) -> Result<()> {
+            let pending = self
+                .pending_loan
+                .get()
+                .flatten()
+                .ok_or(Error::NoPendingLoan)?;
+            if self.env().caller() != pending.lender {
+                return Err(Error::UntrustedLender);
+            }
+            if token != pending.token {
+                return Err(Error::UnexpectedToken {
+                    expected: pending.token,
+                    received: token,
+                });
+            }
+            if amount != pending.amount {
+                return Err(Error::UnexpectedAmount {
+                    expected: pending.amount,
+                    received: amount,
+                });
+            }
+            if fee != pending.fee {
+                return Err(Error::UnexpectedFee {
+                    expected: pending.fee,
+                    received: fee,
+                });
+            }
+            if self.env().hash_bytes::<Keccak256>(data) != pending.data_hash {
+                return Err(Error::UnexpectedData);
+            }
+            Ok(())
+        }
+
+        /// Checks that a batch loan callback matches the loan requested by
+        /// `batch_flash_borrow`.
+        fn ensure_pending_batch_loan(
+            &self,
+            tokens: &[AccountId],
+            amounts: &[Balance],
+            fees: &[Balance],
+            data: &[u8],
+        ) -> Result<()> {
+            let pending = self
+                .pending_batch_loan
+                .get()
+                .flatten()
+                .ok_or(Error::NoPendingLoan)?;
+            if self.env().caller() != pending.lender {
+                return Err(Error::UntrustedLender);
+            }
+            if tokens.len() != pending.tokens.len()
+                || amounts.len() != pending.amounts.len()
+                || fees.len() != pending.fees.len()
+            {
+                return Err(Error::UnexpectedLegs);
+            }
+            for (token, expected) in tokens.iter().zip(&pending.tokens) {
+                if token != expected {
+                    return Err(Error::UnexpectedToken {
+                        expected: *expected,
+                        received: *token,
+                    });
+                }
+            }
+            for (amount, expected) in amounts.iter().zip(&pending.amounts) {
+                if amount != expected {
+                    return Err(Error::UnexpectedAmount {
+                        expected: *expected,
+                        received: *amount,
+                    });
+                }
+            }
+            for (fee, expected) in fees.iter().zip(&pending.fees) {
+                if fee != expected {
+                    return Err(Error::UnexpectedFee {
+                        expected: *expected,
+                        received: *fee,
+                    });
+                }
+            }
+            if self.env().hash_bytes::<Keccak256>(data) != pending.data_hash {
+                return Err(Error::UnexpectedData);
+            }
+            Ok(())
+        }
+
+        /// Executes the logic of `action` with the borrowed funds.
+        fn execute_action(
+            &self,
+            action: Action,
+            tokens: Vec<AccountId>,
+            amounts: Vec<Balance>,
+            fees: Vec<Balance>,
+        ) -> Result<()> {
+            match action {
+                Action::Normal => {
+                    // No logic: the loan is repaid from the borrower's own funds.
+                }
+                Action::Strategy { target, params } => {
+                    self.execute_strategy(target, tokens, amounts, fees, params)?;
+                }
+                Action::Arbitrage {
+                    first_pool,
+                    second_pool,
+                    min_intermediate_out,
+                    min_amount_out,
+                } => {
+                    let ([token], [amount]) = (&tokens[..], &amounts[..]) else {
+                        return Err(Error::InvalidAction);
+                    };
+                    self.execute_arbitrage(
+                        *token,
+                        *amount,
+                        first_pool,
+                        second_pool,
+                        min_intermediate_out,
+                        min_amount_out,
+                    )?;
+                }
+                Action::Liquidation {
+                    market,
+                    borrower,
+                    collateral_pool,
+                    min_amount_out,
+                } => {
+                    let ([token], [amount]) = (&tokens[..], &amounts[..]) else {
+                        return Err(Error::InvalidAction);
+                    };
+                    self.execute_liquidation(
+                        *token,
+                        *amount,
+                        market,
+                        borrower,
+                        collateral_pool,
+                        min_amount_out,
+                    )?;
+                }
+                Action::CollateralSwap {
+                    market,
+                    new_collateral,
+                    collateral_pool,
+                    min_amount_out,
+                } => {
+                    let ([token], [amount], [fee]) = (&tokens[..], &amounts[..], &fees[..]) else {
+                        return Err(Error::InvalidAction);
+                    };
+                    let (collateral, withdrawn) = self._close_position(market, *token, *amount)?;
+                    if Self::other_token(collateral_pool, collateral)? != new_collateral {
+                        return Err(Error::InvalidAction);
+                    }
+                    let bought =
+                        self.swap_on(collateral_pool, collateral, withdrawn, min_amount_out)?;
+                    let repayment = amount.checked_add(*fee).ok_or(Error::Overflow)?;
+                    self._open_position(market, new_collateral, bought, *token, repayment)?;
+                }
+                Action::Refinance {
+                    from_market,
+                    to_market,
+                } => {
+                    let ([token], [amount], [fee]) = (&tokens[..], &amounts[..], &fees[..]) else {
+                        return Err(Error::InvalidAction);
+                    };
+                    let (collateral, withdrawn) =
+                        self._close_position(from_market, *token, *amount)?;
+                    let repayment = amount.checked_add(*fee).ok_or(Error::Overflow)?;
+                    self._open_position(to_market, collateral, withdrawn, *token, repayment)?;
+                }
+            }
+            Ok(())
+        }
+
+        /// Liquidates `amount` of the `token` debt of `borrower` on `market`, and
+        /// sells the seized collateral for `token` on `collateral_pool`.
+        fn execute_liquidation(
+            &self,
+            token: AccountId,
+            amount: Balance,
+            market: AccountId,
+            borrower: AccountId,
+            collateral_pool: AccountId,
+            min_amount_out: Balance,
+        ) -> Result<Balance> {
+            let mut lending_market: ink::contract_ref!(ILendingMarket) = market.into();
+            let collateral = lending_market
+                .position(borrower)
+                .collateral_token
+                .ok_or(Error::InvalidAction)?;
+            if Self::other_token(collateral_pool, collateral)? != token {
+                return Err(Error::InvalidAction);
+            }
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            erc20.approve(market, amount).map_err(Error::ERC20Error)?;
+            let seized = lending_market
+                .liquidate(borrower, token, amount)
+                .map_err(|_| Error::ActionFailed)?;
+            self.swap_on(collateral_pool, collateral, seized, min_amount_out)
+        }
+
+        /// Repays `amount` of the `token` debt of the borrower's position on `market`
+        /// and withdraws its whole collateral.
+        ///
+        /// ## Returns:
+        /// - The collateral token and the amount withdrawn.
+        fn _close_position(
+            &self,
+            market: AccountId,
+            token: AccountId,
+            amount: Balance,
+        ) -> Result<(AccountId, Balance)> {
+            let mut lending_market: ink::contract_ref!(ILendingMarket) = market.into();
+            let position = lending_market.position(self.env().account_id());
+            let collateral = position.collateral_token.ok_or(Error::InvalidAction)?;
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            erc20.approve(market, amount).map_err(Error::ERC20Error)?;
+            lending_market
+                .repay(token, amount)
+                .map_err(|_| Error::ActionFailed)?;
+            lending_market
+                .withdraw_collateral(collateral, position.collateral)
+                .map_err(|_| Error::ActionFailed)?;
+            Ok((collateral, position.collateral))
+        }
+
+        /// Deposits `collateral_amount` of `collateral` on `market` and borrows
+        /// `amount` of `token` against it, on the borrower's own position.
+        fn _open_position(
+            &self,
+            market: AccountId,
+            collateral: AccountId,
+            collateral_amount: Balance,
+            token: AccountId,
+            amount: Balance,
+        ) -> Result<()> {
+            let mut erc20: ink::contract_ref!(IERC20) = collateral.into();
+            erc20
+                .approve(market, collateral_amount)
+                .map_err(Error::ERC20Error)?;
+            let mut lending_market: ink::contract_ref!(ILendingMarket) = market.into();
+            lending_market
+                .deposit_collateral(collateral, collateral_amount)
+                .map_err(|_| Error::ActionFailed)?;
+            lending_market
+                .borrow(token, amount)
+                .map_err(|_| Error::ActionFailed)
+        }
+
+        /// Sells `amount` of `token` on `first_pool` and sells everything bought
+        /// back for `token` on `second_pool`.
+        ///
+        /// Both pools must pair `token` with the same intermediate token.
+        fn execute_arbitrage(
+            &self,
+            token: AccountId,
+            amount: Balance,
+            first_pool: AccountId,
+            second_pool: AccountId,
+            min_intermediate_out: Balance,
+            min_amount_out: Balance,
+        ) -> Result<Balance> {
+            let intermediate = Self::other_token(first_pool, token)?;
+            if Self::other_token(second_pool, intermediate)? != token {
+                return Err(Error::InvalidAction);
+            }
+            let bought = self.swap_on(first_pool, token, amount, min_intermediate_out)?;
+            self.swap_on(second_pool, intermediate, bought, min_amount_out)
+        }
+
+        /// Returns the token `pool` pairs with `token`.
+        fn other_token(pool: AccountId, token: AccountId) -> Result<AccountId> {
+            let pool: ink::contract_ref!(IPair) = pool.into();
+            let (token_0, token_1) = (pool.token_0(), pool.token_1());
+            if token == token_0 {
+                Ok(token_1)
+            } else if token == token_1 {
+                Ok(token_0)
+            } else {
+                Err(Error::InvalidAction)
+            }
+        }
+
+        /// Sells `amount_in` of `token_in` on `pool`, approving exactly the input.
+        fn swap_on(
+            &self,
+            pool: AccountId,
+            token_in: AccountId,
+            amount_in: Balance,
+            min_amount_out: Balance,
+        ) -> Result<Balance> {
+            let mut erc20: ink::contract_ref!(IERC20) = token_in.into();
+            erc20.approve(pool, amount_in).map_err(Error::ERC20Error)?;
+            let mut pool: ink::contract_ref!(IPair) = pool.into();
+            pool.swap(token_in, amount_in, min_amount_out, self.env().account_id())
+                .map_err(|_| Error::ActionFailed)
+        }
+
+        /// Runs the registered strategy `target`, forwarding the loan and `params`.
+        ///
+        /// A strategy contract receives the borrowed tokens before being called,
+        /// while strategy code runs in the borrower context through a delegate call.
+        fn execute_strategy(
+            &self,
+            target: StrategyTarget,
+            tokens: Vec<AccountId>,
+            amounts: Vec<Balance>,
+            fees: Vec<Balance>,
+            params: Vec<u8>,
+        ) -> Result<()> {
+            if !self.is_strategy_registered(target) {
+                return Err(Error::UnregisteredStrategy);
+            }
+            match target {
+                StrategyTarget::Contract(strategy) => {
+                    for (token, amount) in tokens.iter().zip(&amounts) {
+                        let mut erc20: ink::contract_ref!(IERC20) = (*token).into();
+                        erc20
+                            .transfer(strategy, *amount)
+                            .map_err(Error::ERC20Error)?;
+                    }
+                    let strategy: ink::contract_ref!(IFlashLoanStrategy) = strategy.into();
+                    strategy.execute(tokens, amounts, fees, params)
+                }
+                StrategyTarget::Code(code_hash) => build_call::<Environment>()
+                    .delegate(code_hash)
+                    .exec_input(
+                        ExecutionInput::new(Selector::new(ink::selector_bytes!(
+                            "IFlashLoanStrategy::execute"
+                        )))
+                        .push_arg(tokens)
+                        .push_arg(amounts)
+                        .push_arg(fees)
+                        .push_arg(params),
+                    )
+                    .returns::<Result<()>>()
+                    .try_invoke()
+                    .map_err(|_| Error::StrategyFailed)?
+                    .map_err(|_| Error::StrategyFailed)?,
+            }
+        }
+
+        /// Decodes the data into an action, empty data meaning `Action::Normal`.
+        fn decode_action(&self, data: Vec<u8>) -> Result<Action> {
+            if data.is_empty() {
+                return Ok(Action::Normal);
+            }
+            ActionPayload::decode(&mut &data[..])
+                .map(Action::from)
+                .map_err(|_| Error::ScaleDecodingErr)
+        }
+
+        /// Encodes action into data, using the latest payload version.
+        fn encode_action(&self, action: Action) -> Vec<u8> {
+            ActionPayload::V1(action).encode()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn accounts() -> ink::env::test::DefaultAccounts<Environment> {
+            ink::env::test::default_accounts::<Environment>()
+        }
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<Environment>(caller);
+        }
+
+        /// Deploys a borrower trusting `bob` as lender and returns it with its own account.
+        fn borrower() -> (FlashBorrower, AccountId) {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let borrower = FlashBorrower::new(accounts.bob);
+            (borrower, ink::env::account_id::<Environment>())
+        }
+
+        fn data_hash(data: &[u8]) -> [u8; 32] {
+            let mut output = [0u8; 32];
+            ink::env::hash_bytes::<Keccak256>(data, &mut output);
+            output
+        }
+
+        /// Returns the recorded `E` events, each with its topics after the signature.
+        fn recorded_events<E: ink::env::Event + ink::scale::Decode>() -> Vec<(E, Vec<Vec<u8>>)> {
+            let signature = E::SIGNATURE_TOPIC.expect("the event is not anonymous");
+            ink::env::test::recorded_events()
+                .filter(|event| event.topics.first().map(|t| &t[..]) == Some(&signature[..]))
+                .map(|event| {
+                    let decoded = E::decode(&mut &event.data[..]).expect("a matching event");
+                    (decoded, event.topics[1..].to_vec())
+                })
+                .collect()
+        }
+
+        #[ink::test]
+        fn action_payloads_are_versioned() {
+            let (borrower, _) = borrower();
+            let action = Action::Strategy {
+                target: StrategyTarget::Contract(accounts().eve),
+                params: vec![1, 2, 3],
+            };
+
+            let data = ActionPayload::V1(action.clone()).encode();
+            assert_eq!(data[0], 1);
+            assert_eq!(borrower.decode_action(data), Ok(action));
+            assert_eq!(borrower.decode_action(Vec::new()), Ok(Action::Normal));
+            assert_eq!(
+                borrower.decode_action(vec![0]),
+                Err(Error::ScaleDecodingErr)
+            );
+        }
+
+        #[ink::test]
+        fn unregistered_strategy_is_rejected() {
+            let accounts = accounts();
+            let (mut borrower, _) = borrower();
+            let target = StrategyTarget::Contract(accounts.eve);
+
+            assert_eq!(
+                borrower.flash_borrow_with_strategy(
+                    accounts.bob,
+                    accounts.django,
+                    100,
+                    target,
+                    Vec::new(),
+                    None
+                ),
+                Err(Error::UnregisteredStrategy)
+            );
+            assert_eq!(borrower.register_strategy(target), Ok(()));
+            assert_eq!(borrower.unregister_strategy(target), Ok(()));
+            assert_eq!(
+                borrower.flash_borrow_with_strategy(
+                    accounts.bob,
+                    accounts.django,
+                    100,
+                    target,
+                    Vec::new(),
+                    None
+                ),
+                Err(Error::UnregisteredStrategy)
+            );
+        }
+
+        #[ink::test]
+        fn flash_borrow_rejects_malformed_data() {
+            let accounts = accounts();
+            let (mut borrower, _) = borrower();
+
+            assert_eq!(
+                borrower.flash_borrow(accounts.django, 100, vec![0xff], None),
+                Err(Error::ScaleDecodingErr)
+            );
+        }
+
+        #[ink::test]
+        fn batch_flash_borrow_checks_its_arguments() {
+            let accounts = accounts();
+            let (mut borrower, _) = borrower();
+
+            assert_eq!(
+                borrower.batch_flash_borrow(
+                    vec![accounts.django, accounts.eve],
+                    vec![100, 100],
+                    Vec::new(),
+                    Some(vec![0]),
+                ),
+                Err(Error::InconsistentBatchLength)
+            );
+            assert_eq!(
+                borrower.batch_flash_borrow(vec![accounts.django], vec![100], vec![0xff], None),
+                Err(Error::ScaleDecodingErr)
+            );
+        }
+
+        #[ink::test]
+        fn arbitrage_needs_a_single_token_loan() {
+            let accounts = accounts();
+            let (mut borrower, this) = borrower();
+            let data = ActionPayload::V1(Action::Arbitrage {
+                first_pool: accounts.charlie,
+                second_pool: accounts.eve,
+                min_intermediate_out: 0,
+                min_amount_out: 0,
+            })
+            .encode();
+
+            borrower.pending_batch_loan.set(&Some(PendingBatchLoan {
+                lender: accounts.bob,
+                tokens: vec![accounts.django, accounts.frank],
+                amounts: vec![100, 100],
+                fees: vec![1, 1],
+                data_hash: data_hash(&data),
+            }));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_batch_flash_loan(
+                    this,
+                    vec![accounts.django, accounts.frank],
+                    vec![100, 100],
+                    vec![1, 1],
+                    data,
+                ),
+                Err(Error::InvalidAction)
+            );
+        }
+
+        #[ink::test]
+        fn position_actions_need_a_single_token_loan() {
+            let accounts = accounts();
+            let (mut borrower, this) = borrower();
+            let refinance = ActionPayload::V1(Action::Refinance {
+                from_market: accounts.charlie,
+                to_market: accounts.eve,
+            })
+            .encode();
+            assert_eq!(refinance[..2], [1, 6]);
+
+            borrower.pending_batch_loan.set(&Some(PendingBatchLoan {
+                lender: accounts.bob,
+                tokens: vec![accounts.django, accounts.frank],
+                amounts: vec![100, 100],
+                fees: vec![1, 1],
+                data_hash: data_hash(&refinance),
+            }));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_batch_flash_loan(
+                    this,
+                    vec![accounts.django, accounts.frank],
+                    vec![100, 100],
+                    vec![1, 1],
+                    refinance,
+                ),
+                Err(Error::InvalidAction)
+            );
+        }
+
+        #[ink::test]
+        fn only_owner_manages_positions() {
+            let accounts = accounts();
+            let (mut borrower, _) = borrower();
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                borrower.open_position(accounts.eve, accounts.django, 100, accounts.frank, 50),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                borrower.close_position(accounts.eve, accounts.frank, 50),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn callback_without_pending_loan_is_rejected() {
+            let accounts = accounts();
+            let (borrower, this) = borrower();
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_flash_loan(this, accounts.django, 100, 1, Vec::new()),
+                Err(Error::NoPendingLoan)
+            );
+        }
+
+        #[ink::test]
+        fn callback_must_match_pending_loan() {
+            let accounts = accounts();
+            let (mut borrower, this) = borrower();
+            let token = accounts.django;
+            borrower.pending_loan.set(&Some(PendingLoan {
+                lender: accounts.bob,
+                token,
+                amount: 100,
+                fee: 1,
+                data_hash: data_hash(&[]),
+            }));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_flash_loan(this, accounts.eve, 100, 1, Vec::new()),
+                Err(Error::UnexpectedToken {
+                    expected: token,
+                    received: accounts.eve,
+                })
+            );
+            assert_eq!(
+                borrower.on_flash_loan(this, token, 200, 1, Vec::new()),
+                Err(Error::UnexpectedAmount {
+                    expected: 100,
+                    received: 200,
+                })
+            );
+            assert_eq!(
+                borrower.on_flash_loan(this, token, 100, 0, Vec::new()),
+                Err(Error::UnexpectedFee {
+                    expected: 1,
+                    received: 0,
+                })
+            );
+        }
+
+        #[ink::test]
+        fn callback_must_carry_the_requested_data() {
+            let accounts = accounts();
+            let (mut borrower, this) = borrower();
+            let token = accounts.django;
+            let data = borrower.encode_action(Action::Normal);
+            borrower.pending_loan.set(&Some(PendingLoan {
+                lender: accounts.bob,
+                token,
+                amount: 100,
+                fee: 1,
+                data_hash: data_hash(&data),
+            }));
+
+            set_caller(accounts.bob);
+            let spoofed = borrower.encode_action(Action::Strategy {
+                target: StrategyTarget::Contract(accounts.eve),
+                params: Vec::new(),
+            });
+            assert_eq!(
+                borrower.on_flash_loan(this, token, 100, 1, spoofed),
+                Err(Error::UnexpectedData)
+            );
+        }
+
+        #[ink::test]
+        fn executed_loans_are_indexed_by_lender_and_token() {
+            let accounts = accounts();
+            let (mut borrower, this) = borrower();
+            let data = borrower.encode_action(Action::Normal);
+            borrower.pending_loan.set(&Some(PendingLoan {
+                lender: accounts.bob,
+                token: accounts.django,
+                amount: 100,
+                fee: 1,
+                data_hash: data_hash(&data),
+            }));
+            borrower.pending_batch_loan.set(&Some(PendingBatchLoan {
+                lender: accounts.bob,
+                tokens: vec![accounts.eve],
+                amounts: vec![200],
+                fees: vec![2],
+                data_hash: data_hash(&data),
+            }));
+
+            set_caller(accounts.bob);
+            assert!(borrower
+                .on_flash_loan(this, accounts.django, 100, 1, data.clone())
+                .is_ok());
+            assert!(borrower
+                .on_batch_flash_loan(this, vec![accounts.eve], vec![200], vec![2], data)
+                .is_ok());
+
+            let executed = recorded_events::<LoanExecuted>();
+            assert_eq!(executed.len(), 2);
+            for ((event, topics), (token, amount, fee)) in executed
+                .iter()
+                .zip([(accounts.django, 100, 1), (accounts.eve, 200, 2)])
+            {
+                assert_eq!(event.lender, accounts.bob);
+                assert_eq!((event.token, event.amount, event.fee), (token, amount, fee));
+                assert_eq!(topics, &vec![accounts.bob.encode(), token.encode()]);
+            }
+        }
+
+        #[ink::test]
+        fn batch_callback_without_pending_loan_is_rejected() {
+            let accounts = accounts();
+            let (borrower, this) = borrower();
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_batch_flash_loan(
+                    this,
+                    vec![accounts.django],
+                    vec![100],
+                    vec![1],
+                    Vec::new()
+                ),
+                Err(Error::NoPendingLoan)
+            );
+        }
+
+        #[ink::test]
+        fn batch_callback_must_match_pending_loan() {
+            let accounts = accounts();
+            let (mut borrower, this) = borrower();
+            let tokens = vec![accounts.django, accounts.eve];
+            let data = borrower.encode_action(Action::Normal);
+            borrower.pending_batch_loan.set(&Some(PendingBatchLoan {
+                lender: accounts.bob,
+                tokens: tokens.clone(),
+                amounts: vec![100, 200],
+                fees: vec![1, 2],
+                data_hash: data_hash(&data),
+            }));
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                borrower.on_batch_flash_loan(
+                    this,
+                    tokens.clone(),
+                    vec![100, 200],
+                    vec![1, 2],
+                    data.clone()
+                ),
+                Err(Error::UntrustedLender)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_batch_flash_loan(
+                    this,
+                    vec![accounts.django],
+                    vec![100],
+                    vec![1],
+                    data.clone()
+                ),
+                Err(Error::UnexpectedLegs)
+            );
+            assert_eq!(
+                borrower.on_batch_flash_loan(
+                    this,
+                    vec![accounts.django, accounts.frank],
+                    vec![100, 200],
+                    vec![1, 2],
+                    data.clone()
+                ),
+                Err(Error::UnexpectedToken {
+                    expected: accounts.eve,
+                    received: accounts.frank,
+                })
+            );
+            assert_eq!(
+                borrower.on_batch_flash_loan(
+                    this,
+                    tokens.clone(),
+                    vec![100, 300],
+                    vec![1, 2],
+                    data.clone()
+                ),
+                Err(Error::UnexpectedAmount {
+                    expected: 200,
+                    received: 300,
+                })
+            );
+            assert_eq!(
+                borrower.on_batch_flash_loan(
+                    this,
+                    tokens.clone(),
+                    vec![100, 200],
+                    vec![1, 0],
+                    data
+                ),
+                Err(Error::UnexpectedFee {
+                    expected: 2,
+                    received: 0,
+                })
+            );
+            assert_eq!(
+                borrower.on_batch_flash_loan(this, tokens, vec![100, 200], vec![1, 2], vec![0xff]),
+                Err(Error::UnexpectedData)
+            );
+        }
+
+        #[ink::test]
+        fn callbacks_are_accepted_only_from_the_requested_trusted_lender() {
+            let accounts = accounts();
+            let (mut borrower, this) = borrower();
+            let token = accounts.django;
+            borrower.pending_loan.set(&Some(PendingLoan {
+                lender: accounts.bob,
+                token,
+                amount: 100,
+                fee: 1,
+                data_hash: data_hash(&[]),
+            }));
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                borrower.on_flash_loan(this, token, 100, 1, Vec::new()),
+                Err(Error::UntrustedLender)
+            );
+            assert_eq!(
+                borrower.add_trusted_lender(accounts.charlie),
+                Err(Error::Unauthorized)
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(borrower.add_trusted_lender(accounts.charlie), Ok(()));
+            assert!(borrower.is_trusted_lender(accounts.charlie));
+
+            // Trusted, but not the lender the pending loan was requested from.
+            set_caller(accounts.charlie);
+            assert_eq!(
+                borrower.on_flash_loan(this, token, 100, 1, Vec::new()),
+                Err(Error::UntrustedLender)
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use amm::{Pair, PairRef};
+        use erc20::{Erc20, Erc20Ref};
+        use flash_lender::{FlashLender, FlashLenderRef};
+        use forward_strategy::ForwardStrategyRef;
+        use ilending::{Position, PRICE_PRECISION};
+        use ink_e2e::events::{ContractEmitted, EventWithTopics};
+        use ink_e2e::{ContractsBackend, H256};
+        use lending_market::{LendingMarket, LendingMarketRef};
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        /// Returns the `E` events emitted by `contract` during a call, each with its
+        /// topics after the signature.
+        fn emitted_events<E: ink::env::Event + ink::scale::Decode>(
+            events: Vec<EventWithTopics<ContractEmitted<Environment>>>,
+            contract: AccountId,
+        ) -> Vec<(E, Vec<H256>)> {
+            let signature = E::SIGNATURE_TOPIC.expect("the event is not anonymous");
+            events
+                .into_iter()
+                .filter(|event| event.event.contract == contract)
+                .filter(|event| event.topics.first().map(|t| t.0) == Some(signature))
+                .map(|event| {
+                    let decoded = E::decode(&mut &event.event.data[..]).expect("a matching event");
+                    (decoded, event.topics[1..].to_vec())
+                })
+                .collect()
+        }
+
+        /// Returns the topic indexing `account`.
+        fn topic(account: AccountId) -> H256 {
+            H256::from_slice(&account.encode())
+        }
+
+        #[ink_e2e::test]
+        async fn flash_borrow_repays_amount_plus_fee<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+
+            let mut borrower_constructor = FlashBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+
+            // Fund the lender with liquidity and the borrower with enough to pay the fee.
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(lender.account_id, 500_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(borrower.account_id, 1_000),
+                )
+                .submit()
+                .await
+                .expect("borrower funding failed");
+
+            // The default action makes no profit, so even a zero minimum reverts a
+            // loan whose fee would be paid from reserves.
+            for (min_profit, needed) in [(0, 1_000), (1, 1_001)] {
+                let unprofitable = client
+                    .call(
+                        &ink_e2e::alice(),
+                        &borrower_call.flash_borrow(
+                            erc20.account_id,
+                            100_000,
+                            Vec::new(),
+                            Some(min_profit),
+                        ),
+                    )
+                    .dry_run()
+                    .await?;
+                assert_eq!(
+                    unprofitable.return_value(),
+                    Err(Error::InsufficientProfit {
+                        balance: 900,
+                        needed,
+                    })
+                );
+            }
+
+            let flash_borrow = client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow(erc20.account_id, 100_000, Vec::new(), None),
+                )
+                .submit()
+                .await
+                .expect("flash_borrow failed");
+
+            let expected_topics = vec![topic(lender.account_id), topic(erc20.account_id)];
+            let initiated = emitted_events::<LoanInitiated>(
+                flash_borrow.contract_emitted_events()?,
+                borrower.account_id,
+            );
+            assert_eq!(initiated.len(), 1);
+            let (event, topics) = &initiated[0];
+            assert_eq!((event.amount, event.fee), (100_000, 100));
+            assert_eq!(topics, &expected_topics);
+            let executed = emitted_events::<LoanExecuted>(
+                flash_borrow.contract_emitted_events()?,
+                borrower.account_id,
+            );
+            assert_eq!(executed.len(), 1);
+            let (event, topics) = &executed[0];
+            assert_eq!((event.amount, event.fee), (100_000, 100));
+            assert_eq!(topics, &expected_topics);
+
+            let lender_balance = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(lender.account_id))
+                .dry_run()
+                .await?;
+            assert_eq!(lender_balance.return_value(), 500_100);
+            let borrower_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.balance_of(borrower.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(borrower_balance.return_value(), 900);
+            let allowance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.allowance(borrower.account_id, lender.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(allowance.return_value(), 0);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn flash_borrow_fails_without_fee_funds<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+
+            let mut borrower_constructor = FlashBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(lender.account_id, 500_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+
+            let result = client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow(erc20.account_id, 100_000, Vec::new(), None),
+                )
+                .dry_run()
+                .await?;
+            assert!(result.return_value().is_err());
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn liquidity_provider_earns_flash_fees<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut lender_call = lender.call_builder::<FlashLender>();
+
+            let mut borrower_constructor = FlashBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.approve(lender.account_id, 500_000),
+                )
+                .submit()
+                .await
+                .expect("approve failed");
+            let shares = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.deposit(erc20.account_id, 500_000),
+                )
+                .submit()
+                .await
+                .expect("deposit failed")
+                .return_value()
+                .expect("deposit returned an error");
+            assert_eq!(shares, 500_000);
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(borrower.account_id, 1_000),
+                )
+                .submit()
+                .await
+                .expect("borrower funding failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow(erc20.account_id, 100_000, Vec::new(), None),
+                )
+                .submit()
+                .await
+                .expect("flash_borrow failed");
+
+            let withdrawn = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.withdraw(erc20.account_id, shares),
+                )
+                .submit()
+                .await
+                .expect("withdraw failed")
+                .return_value()
+                .expect("withdraw returned an error");
+            // The whole fee is earned by the provider; the virtual shares priced in
+            // by the lender keep the rounding remainder.
+            assert_eq!(withdrawn, 500_099);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn strategy_contract_receives_and_returns_the_loan<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+
+            let mut borrower_constructor = FlashBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+
+            let mut strategy_constructor = ForwardStrategyRef::new();
+            let strategy = client
+                .instantiate(
+                    "forward_strategy",
+                    &ink_e2e::alice(),
+                    &mut strategy_constructor,
+                )
+                .submit()
+                .await
+                .expect("forward_strategy instantiate failed");
+            let target = StrategyTarget::Contract(strategy.account_id);
+
+            // The strategy pays the fee, handing the loan back to the borrower.
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(lender.account_id, 500_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(strategy.account_id, 1_000),
+                )
+                .submit()
+                .await
+                .expect("strategy funding failed");
+            client
+                .call(&ink_e2e::alice(), &borrower_call.register_strategy(target))
+                .submit()
+                .await
+                .expect("register_strategy failed");
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow_with_strategy(
+                        lender.account_id,
+                        erc20.account_id,
+                        100_000,
+                        target,
+                        borrower.account_id.encode(),
+                        None,
+                    ),
+                )
+                .submit()
+                .await
+                .expect("flash_borrow_with_strategy failed");
+
+            let lender_balance = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(lender.account_id))
+                .dry_run()
+                .await?;
+            assert_eq!(lender_balance.return_value(), 500_100);
+            let strategy_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.balance_of(strategy.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(strategy_balance.return_value(), 900);
+            let borrower_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.balance_of(borrower.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(borrower_balance.return_value(), 0);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn strategy_code_runs_in_the_borrower_context<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+
+            let mut borrower_constructor = FlashBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+
+            // The strategy code is only uploaded, never instantiated.
+            let code_hash = client
+                .upload("forward_strategy", &ink_e2e::alice())
+                .submit()
+                .await
+                .expect("forward_strategy upload failed")
+                .code_hash;
+            let target = StrategyTarget::Code(code_hash);
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(lender.account_id, 500_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(borrower.account_id, 200_000),
+                )
+                .submit()
+                .await
+                .expect("borrower funding failed");
+            client
+                .call(&ink_e2e::alice(), &borrower_call.register_strategy(target))
+                .submit()
+                .await
+                .expect("register_strategy failed");
+
+            // Run by the borrower, the strategy pays bob from the borrower balance.
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow_with_strategy(
+                        lender.account_id,
+                        erc20.account_id,
+                        100_000,
+                        target,
+                        bob.encode(),
+                        None,
+                    ),
+                )
+                .submit()
+                .await
+                .expect("flash_borrow_with_strategy failed");
+
+            let lender_balance = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(lender.account_id))
+                .dry_run()
+                .await?;
+            assert_eq!(lender_balance.return_value(), 500_100);
+            let bob_balance = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(bob))
+                .dry_run()
+                .await?;
+            assert_eq!(bob_balance.return_value(), 100_100);
+            let borrower_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.balance_of(borrower.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(borrower_balance.return_value(), 99_800);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn only_owner_and_operators_borrow_and_sweep<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let alice = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+
+            let mut borrower_constructor = FlashBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(lender.account_id, 500_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(borrower.account_id, 1_000),
+                )
+                .submit()
+                .await
+                .expect("borrower funding failed");
+
+            let unauthorized = client
+                .call(
+                    &ink_e2e::bob(),
+                    &borrower_call.flash_borrow(erc20.account_id, 100_000, Vec::new(), None),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(unauthorized.return_value(), Err(Error::Unauthorized));
+
+            client
+                .call(&ink_e2e::alice(), &borrower_call.set_operator(bob, true))
+                .submit()
+                .await
+                .expect("set_operator failed");
+            client
+                .call(
+                    &ink_e2e::bob(),
+                    &borrower_call.flash_borrow(erc20.account_id, 100_000, Vec::new(), None),
+                )
+                .submit()
+                .await
+                .expect("operator flash_borrow failed");
+
+            let unauthorized = client
+                .call(&ink_e2e::bob(), &borrower_call.sweep(erc20.account_id))
+                .dry_run()
+                .await?;
+            assert_eq!(unauthorized.return_value(), Err(Error::Unauthorized));
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.withdraw(erc20.account_id, 400, bob),
+                )
+                .submit()
+                .await
+                .expect("withdraw failed");
+            let swept = client
+                .call(&ink_e2e::alice(), &borrower_call.sweep(erc20.account_id))
+                .submit()
+                .await
+                .expect("sweep failed")
+                .return_value()
+                .expect("sweep returned an error");
+            assert_eq!(swept, 500);
+
+            let bob_balance = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(bob))
+                .dry_run()
+                .await?;
+            assert_eq!(bob_balance.return_value(), 400);
+            let alice_balance = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(alice))
+                .dry_run()
+                .await?;
+            assert_eq!(alice_balance.return_value(), 499_500);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn flash_borrow_from_uses_the_named_lender<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut default_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let default_lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut default_constructor)
+                .submit()
+                .await
+                .expect("default lender instantiate failed");
+            let mut cheap_constructor = FlashLenderRef::new(vec![erc20.account_id], 5);
+            let cheap_lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut cheap_constructor)
+                .submit()
+                .await
+                .expect("cheap lender instantiate failed");
+
+            let mut borrower_constructor = FlashBorrowerRef::new(default_lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+
+            for lender in [default_lender.account_id, cheap_lender.account_id] {
+                client
+                    .call(&ink_e2e::alice(), &erc20_call.transfer(lender, 500_000))
+                    .submit()
+                    .await
+                    .expect("lender funding failed");
+            }
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(borrower.account_id, 1_000),
+                )
+                .submit()
+                .await
+                .expect("borrower funding failed");
+
+            let untrusted = client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow_from(
+                        cheap_lender.account_id,
+                        erc20.account_id,
+                        100_000,
+                        ActionPayload::V1(Action::Normal).encode(),
+                        None,
+                    ),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(untrusted.return_value(), Err(Error::UntrustedLender));
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.add_trusted_lender(cheap_lender.account_id),
+                )
+                .submit()
+                .await
+                .expect("add_trusted_lender failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow_from(
+                        cheap_lender.account_id,
+                        erc20.account_id,
+                        100_000,
+                        ActionPayload::V1(Action::Normal).encode(),
+                        None,
+                    ),
+                )
+                .submit()
+                .await
+                .expect("flash_borrow_from failed");
+
+            for (lender, expected) in [
+                (default_lender.account_id, 500_000),
+                (cheap_lender.account_id, 500_050),
+            ] {
+                let balance = client
+                    .call(&ink_e2e::alice(), &erc20_call.balance_of(lender))
+                    .dry_run()
+                    .await?;
+                assert_eq!(balance.return_value(), expected);
+            }
+            let last_action = client
+                .call(&ink_e2e::alice(), &borrower_call.last_action())
+                .dry_run()
+                .await?;
+            assert_eq!(last_action.return_value(), Action::Normal);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn arbitrage_keeps_the_spread_between_pools<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut token_a_constructor = Erc20Ref::new(1_000_000);
+            let token_a = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut token_a_constructor)
+                .submit()
+                .await
+                .expect("token A instantiate failed");
+            let mut token_a_call = token_a.call_builder::<Erc20>();
+            let mut token_b_constructor = Erc20Ref::new(1_000_000);
+            let token_b = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut token_b_constructor)
+                .submit()
+                .await
+                .expect("token B instantiate failed");
+            let mut token_b_call = token_b.call_builder::<Erc20>();
+
+            // A is worth 1.2 B on the first pool, and B is worth 1.2 A on the second.
+            let mut pools = Vec::new();
+            for (reserve_a, reserve_b) in [(100_000, 120_000), (120_000, 100_000)] {
+                let mut pair_constructor = PairRef::new(token_a.account_id, token_b.account_id);
+                let pair = client
+                    .instantiate("amm", &ink_e2e::alice(), &mut pair_constructor)
+                    .submit()
+                    .await
+                    .expect("pair instantiate failed");
+                let mut pair_call = pair.call_builder::<Pair>();
+                for token_call in [&mut token_a_call, &mut token_b_call] {
+                    client
+                        .call(
+                            &ink_e2e::alice(),
+                            &token_call.approve(pair.account_id, u128::MAX),
+                        )
+                        .submit()
+                        .await
+                        .expect("approve failed");
+                }
+                client
+                    .call(
+                        &ink_e2e::alice(),
+                        &pair_call.add_liquidity(reserve_a, reserve_b, 0),
+                    )
+                    .submit()
+                    .await
+                    .expect("add_liquidity failed");
+                pools.push(pair.account_id);
+            }
+
+            let mut lender_constructor = FlashLenderRef::new(vec![token_a.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_a_call.transfer(lender.account_id, 500_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+
+            let mut borrower_constructor = FlashBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+
+            let arbitrage = |min_amount_out| {
+                ActionPayload::V1(Action::Arbitrage {
+                    first_pool: pools[0],
+                    second_pool: pools[1],
+                    min_intermediate_out: 5_697,
+                    min_amount_out,
+                })
+                .encode()
+            };
+            let slippage = client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow(
+                        token_a.account_id,
+                        5_000,
+                        arbitrage(6_450),
+                        Some(0),
+                    ),
+                )
+                .dry_run()
+                .await?;
+            assert!(slippage.return_value().is_err());
+
+            // The borrower holds no funds: the spread pays the fee and the profit.
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow(
+                        token_a.account_id,
+                        5_000,
+                        arbitrage(6_449),
+                        Some(1_000),
+                    ),
+                )
+                .submit()
+                .await
+                .expect("arbitrage flash_borrow failed");
+
+            let profit = client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_a_call.balance_of(borrower.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(profit.return_value(), 6_449 - 5_005);
+            let lender_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_a_call.balance_of(lender.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(lender_balance.return_value(), 500_005);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn liquidation_repays_the_loan_from_seized_collateral<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let mut debt_constructor = Erc20Ref::new(10_000_000);
+            let debt_token = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut debt_constructor)
+                .submit()
+                .await
+                .expect("debt token instantiate failed");
+            let mut debt_call = debt_token.call_builder::<Erc20>();
+            let mut collateral_constructor = Erc20Ref::new(10_000_000);
+            let collateral_token = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut collateral_constructor)
+                .submit()
+                .await
+                .expect("collateral token instantiate failed");
+            let mut collateral_call = collateral_token.call_builder::<Erc20>();
+
+            // The collateral trades at 0.8 debt tokens.
+            let mut pair_constructor =
+                PairRef::new(collateral_token.account_id, debt_token.account_id);
+            let pair = client
+                .instantiate("amm", &ink_e2e::alice(), &mut pair_constructor)
+                .submit()
+                .await
+                .expect("pair instantiate failed");
+            let mut pair_call = pair.call_builder::<Pair>();
+            for token_call in [&mut collateral_call, &mut debt_call] {
+                client
+                    .call(
+                        &ink_e2e::alice(),
+                        &token_call.approve(pair.account_id, u128::MAX),
+                    )
+                    .submit()
+                    .await
+                    .expect("approve failed");
+            }
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &pair_call.add_liquidity(1_000_000, 800_000, 0),
+                )
+                .submit()
+                .await
+                .expect("add_liquidity failed");
+
+            let mut market_constructor = LendingMarketRef::new(7_500, 500);
+            let market = client
+                .instantiate("lending_market", &ink_e2e::alice(), &mut market_constructor)
+                .submit()
+                .await
+                .expect("lending_market instantiate failed");
+            let mut market_call = market.call_builder::<LendingMarket>();
+            for token in [collateral_token.account_id, debt_token.account_id] {
+                client
+                    .call(
+                        &ink_e2e::alice(),
+                        &market_call.set_price(token, PRICE_PRECISION),
+                    )
+                    .submit()
+                    .await
+                    .expect("set_price failed");
+            }
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &debt_call.transfer(market.account_id, 100_000),
+                )
+                .submit()
+                .await
+                .expect("market funding failed");
+
+            // Bob borrows 7 000 against 10 000 of collateral.
+            client
+                .call(&ink_e2e::alice(), &collateral_call.transfer(bob, 10_000))
+                .submit()
+                .await
+                .expect("bob funding failed");
+            client
+                .call(
+                    &ink_e2e::bob(),
+                    &collateral_call.approve(market.account_id, 10_000),
+                )
+                .submit()
+                .await
+                .expect("approve failed");
+            client
+                .call(
+                    &ink_e2e::bob(),
+                    &market_call.deposit_collateral(collateral_token.account_id, 10_000),
+                )
+                .submit()
+                .await
+                .expect("deposit_collateral failed");
+            client
+                .call(
+                    &ink_e2e::bob(),
+                    &market_call.borrow(debt_token.account_id, 7_000),
+                )
+                .submit()
+                .await
+                .expect("borrow failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &market_call.set_price(collateral_token.account_id, PRICE_PRECISION * 8 / 10),
+                )
+                .submit()
+                .await
+                .expect("set_price failed");
+
+            let mut lender_constructor = FlashLenderRef::new(vec![debt_token.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &debt_call.transfer(lender.account_id, 500_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+
+            let mut borrower_constructor = FlashBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+
+            // 5 000 of debt seizes 6 562 of collateral (5% bonus), sold for 5 199.
+            let liquidation = ActionPayload::V1(Action::Liquidation {
+                market: market.account_id,
+                borrower: bob,
+                collateral_pool: pair.account_id,
+                min_amount_out: 5_199,
+            })
+            .encode();
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow(debt_token.account_id, 5_000, liquidation, Some(0)),
+                )
+                .submit()
+                .await
+                .expect("liquidation flash_borrow failed");
+
+            let profit = client
+                .call(
+                    &ink_e2e::alice(),
+                    &debt_call.balance_of(borrower.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(profit.return_value(), 5_199 - 5_005);
+            let position = client
+                .call(&ink_e2e::alice(), &market_call.position(bob))
+                .dry_run()
+                .await?
+                .return_value();
+            assert_eq!(position.debt, 2_000);
+            assert_eq!(position.collateral, 10_000 - 6_562);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn position_is_refinanced_then_moved_to_new_collateral<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut tokens = Vec::new();
+            for _ in 0..3 {
+                let mut constructor = Erc20Ref::new(10_000_000);
+                let token = client
+                    .instantiate("erc20", &ink_e2e::alice(), &mut constructor)
+                    .submit()
+                    .await
+                    .expect("erc20 instantiate failed");
+                tokens.push(token.account_id);
+            }
+            let (debt_token, collateral_token, new_collateral_token) =
+                (tokens[0], tokens[1], tokens[2]);
+            let erc20_call = |token: AccountId| ink_e2e::create_call_builder::<Erc20>(token);
+
+            let mut markets = Vec::new();
+            for collateral_factor in [7_500, 8_000] {
+                let mut constructor = LendingMarketRef::new(collateral_factor, 500);
+                let market = client
+                    .instantiate("lending_market", &ink_e2e::alice(), &mut constructor)
+                    .submit()
+                    .await
+                    .expect("lending_market instantiate failed");
+                let mut market_call = market.call_builder::<LendingMarket>();
+                for token in &tokens {
+                    client
+                        .call(
+                            &ink_e2e::alice(),
+                            &market_call.set_price(*token, PRICE_PRECISION),
+                        )
+                        .submit()
+                        .await
+                        .expect("set_price failed");
+                }
+                client
+                    .call(
+                        &ink_e2e::alice(),
+                        &erc20_call(debt_token).transfer(market.account_id, 100_000),
+                    )
+                    .submit()
+                    .await
+                    .expect("market funding failed");
+                markets.push(market.account_id);
+            }
+            let (old_market, new_market) = (markets[0], markets[1]);
+
+            let mut pair_constructor = PairRef::new(collateral_token, new_collateral_token);
+            let pair = client
+                .instantiate("amm", &ink_e2e::alice(), &mut pair_constructor)
+                .submit()
+                .await
+                .expect("pair instantiate failed");
+            let mut pair_call = pair.call_builder::<Pair>();
+            for token in [collateral_token, new_collateral_token] {
+                client
+                    .call(
+                        &ink_e2e::alice(),
+                        &erc20_call(token).approve(pair.account_id, u128::MAX),
+                    )
+                    .submit()
+                    .await
+                    .expect("approve failed");
+            }
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &pair_call.add_liquidity(100_000, 100_000, 0),
+                )
+                .submit()
+                .await
+                .expect("add_liquidity failed");
+
+            let mut lender_constructor = FlashLenderRef::new(vec![debt_token], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call(debt_token).transfer(lender.account_id, 500_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+
+            let mut borrower_constructor = FlashBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call(collateral_token).transfer(borrower.account_id, 10_000),
+                )
+                .submit()
+                .await
+                .expect("borrower funding failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.open_position(
+                        old_market,
+                        collateral_token,
+                        10_000,
+                        debt_token,
+                        5_000,
+                    ),
+                )
+                .submit()
+                .await
+                .expect("open_position failed");
+
+            // The 5 000 debt moves to the new market, grown by the 5 fee.
+            let refinance = ActionPayload::V1(Action::Refinance {
+                from_market: old_market,
+                to_market: new_market,
+            })
+            .encode();
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow(debt_token, 5_000, refinance, None),
+                )
+                .submit()
+                .await
+                .expect("refinance flash_borrow failed");
+
+            // The 10 000 collateral is sold for 9 066 of the new collateral.
+            let collateral_swap = ActionPayload::V1(Action::CollateralSwap {
+                market: new_market,
+                new_collateral: new_collateral_token,
+                collateral_pool: pair.account_id,
+                min_amount_out: 9_066,
+            })
+            .encode();
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow(debt_token, 5_005, collateral_swap, None),
+                )
+                .submit()
+                .await
+                .expect("collateral swap flash_borrow failed");
+
+            for (market, expected) in [
+                (old_market, Position::default()),
+                (
+                    new_market,
+                    Position {
+                        collateral_token: Some(new_collateral_token),
+                        collateral: 9_066,
+                        debt_token: Some(debt_token),
+                        debt: 5_010,
+                    },
+                ),
+            ] {
+                let market_call = ink_e2e::create_call_builder::<LendingMarket>(market);
+                let position = client
+                    .call(
+                        &ink_e2e::alice(),
+                        &market_call.position(borrower.account_id),
+                    )
+                    .dry_run()
+                    .await?;
+                assert_eq!(position.return_value(), expected);
+            }
+            let debt_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call(debt_token).balance_of(borrower.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(debt_balance.return_value(), 5_000);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn batch_flash_borrow_repays_every_leg<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut token_a_constructor = Erc20Ref::new(1_000_000);
+            let token_a = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut token_a_constructor)
+                .submit()
+                .await
+                .expect("token A instantiate failed");
+            let mut token_a_call = token_a.call_builder::<Erc20>();
+            let mut token_b_constructor = Erc20Ref::new(1_000_000);
+            let token_b = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut token_b_constructor)
+                .submit()
+                .await
+                .expect("token B instantiate failed");
+            let mut token_b_call = token_b.call_builder::<Erc20>();
+
+            let mut lender_constructor =
+                FlashLenderRef::new(vec![token_a.account_id, token_b.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+
+            let mut borrower_constructor = FlashBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+
+            for token_call in [&mut token_a_call, &mut token_b_call] {
+                client
+                    .call(
+                        &ink_e2e::alice(),
+                        &token_call.transfer(lender.account_id, 500_000),
+                    )
+                    .submit()
+                    .await
+                    .expect("lender funding failed");
+                client
+                    .call(
+                        &ink_e2e::alice(),
+                        &token_call.transfer(borrower.account_id, 1_000),
+                    )
+                    .submit()
+                    .await
+                    .expect("borrower funding failed");
+            }
+
+            // The default action makes no profit, so any minimum reverts the loan.
+            let unprofitable = client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.batch_flash_borrow(
+                        vec![token_a.account_id, token_b.account_id],
+                        vec![100_000, 200_000],
+                        Vec::new(),
+                        Some(vec![0, 1]),
+                    ),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(
+                unprofitable.return_value(),
+                Err(Error::InsufficientProfit {
+                    balance: 800,
+                    needed: 1_001,
+                })
+            );
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.batch_flash_borrow(
+                        vec![token_a.account_id, token_b.account_id],
+                        vec![100_000, 200_000],
+                        Vec::new(),
+                        None,
+                    ),
+                )
+                .submit()
+                .await
+                .expect("batch_flash_borrow failed");
+
+            for (token_call, expected) in [(&token_a_call, 500_100), (&token_b_call, 500_200)] {
+                let lender_balance = client
+                    .call(&ink_e2e::alice(), &token_call.balance_of(lender.account_id))
+                    .dry_run()
+                    .await?;
+                assert_eq!(lender_balance.return_value(), expected);
+            }
+
+            Ok(())
         }
     }
 }
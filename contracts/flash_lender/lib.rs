@@ -1,23 +1,203 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
+pub use self::flash_lender::{FeeSchedule, FlashLender, FlashLenderRef};
+
 #[ink::contract]
 mod flash_lender {
     use ierc20::IERC20;
     use ierc3156::{
+        ierc3156_batch_flash_borrower::IERC3156BatchFlashBorrower,
+        ierc3156_batch_flash_lender::IERC3156BatchFlashLender,
         ierc3156_flash_borrower::IERC3156FlashBorrower,
-        ierc3156_flash_lender::{Error, IERC3156FlashLender, Result},
+        ierc3156_flash_lender::{Error, IERC3156FlashLender, Result, NATIVE_TOKEN},
+        ierc3156_native_flash_borrower::IERC3156NativeFlashBorrower,
+        ierc3156_native_flash_lender::IERC3156NativeFlashLender,
+        ierc7399_flash_lender::IERC7399FlashLender,
+    };
+    use ink::{
+        codegen::TraitCallBuilder,
+        env::{
+            call::{build_call, ExecutionInput, Selector},
+            hash::Keccak256,
+            CallFlags,
+        },
+        prelude::vec::Vec,
+        storage::Mapping,
     };
-    use ink::{env::hash::Keccak256, storage::Mapping};
+
+    /// Fee configuration of a supported token.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct FeeSchedule {
+        /// Percentage of the loan `amount` charged (1 == 0.01%).
+        pub rate: u128,
+        /// Minimum absolute fee charged on every loan.
+        pub min_fee: u128,
+        /// Maximum absolute fee charged on every loan, if capped.
+        pub max_fee: Option<u128>,
+    }
+
+    impl FeeSchedule {
+        /// A schedule charging `rate` with no minimum and no cap.
+        pub fn flat(rate: u128) -> Self {
+            Self {
+                rate,
+                min_fee: 0,
+                max_fee: None,
+            }
+        }
+    }
 
     #[ink(storage)]
     pub struct FlashLender {
-        supported_tokens: Mapping<AccountId, bool>,
-        fee: u128, // 1 = 0.01%
+        /// Fee schedule of each token supported for flash lending.
+        supported_tokens: Mapping<AccountId, FeeSchedule>,
+        /// Account allowed to manage the lender configuration.
+        owner: AccountId,
+        /// Account nominated by the owner, pending to accept the ownership.
+        pending_owner: Option<AccountId>,
+        /// Total liquidity provider shares issued for each token.
+        total_shares: Mapping<AccountId, u128>,
+        /// Liquidity provider shares held for each (token, provider).
+        shares: Mapping<(AccountId, AccountId), u128>,
+        /// Tokens owned by the liquidity providers of each token: their deposits
+        /// plus their part of the flash fees.
+        lp_assets: Mapping<AccountId, u128>,
+        /// Tokens with a loan in progress.
+        active_loans: Mapping<AccountId, ()>,
+        /// Receivers trusted to call back into the lender during their callback.
+        reentrant_receivers: Mapping<AccountId, ()>,
+        /// Account allowed to pause lending besides the owner.
+        guardian: Option<AccountId>,
+        /// Whether lending of every token is paused.
+        paused: bool,
+        /// Tokens whose lending is paused.
+        paused_tokens: Mapping<AccountId, ()>,
+    }
+
+    /// Scale of the value returned by `share_price` (1 share == 1 token).
+    pub const SHARE_PRICE_PRECISION: u128 = 1_000_000_000_000;
+
+    /// Virtual shares and assets added to every pool when pricing shares, so the
+    /// first deposit cannot be diluted by inflating the share price.
+    pub const VIRTUAL_LIQUIDITY: u128 = 1_000;
+
+    /// Emitted when a flash loan is repaid.
+    #[ink(event)]
+    pub struct FlashLoan {
+        #[ink(topic)]
+        receiver: AccountId,
+        #[ink(topic)]
+        initiator: AccountId,
+        #[ink(topic)]
+        token: AccountId,
+        amount: u128,
+        fee: u128,
+    }
+
+    /// Emitted when the owner nominates a new owner.
+    #[ink(event)]
+    pub struct OwnershipTransferStarted {
+        #[ink(topic)]
+        previous_owner: AccountId,
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
+
+    /// Emitted when the nominated owner accepts the ownership.
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous_owner: AccountId,
+        #[ink(topic)]
+        new_owner: AccountId,
+    }
+
+    /// Emitted when a token is added to the supported tokens.
+    #[ink(event)]
+    pub struct TokenSupported {
+        #[ink(topic)]
+        token: AccountId,
+    }
+
+    /// Emitted when a token is removed from the supported tokens.
+    #[ink(event)]
+    pub struct TokenRemoved {
+        #[ink(topic)]
+        token: AccountId,
+    }
+
+    /// Emitted when the fee schedule of a token changes.
+    #[ink(event)]
+    pub struct FeeUpdated {
+        #[ink(topic)]
+        token: AccountId,
+        old_fee: FeeSchedule,
+        new_fee: FeeSchedule,
+    }
+
+    /// Emitted when a liquidity provider deposits tokens into the lender.
+    #[ink(event)]
+    pub struct Deposit {
+        #[ink(topic)]
+        token: AccountId,
+        #[ink(topic)]
+        provider: AccountId,
+        amount: u128,
+        shares: u128,
+    }
+
+    /// Emitted when a liquidity provider withdraws tokens from the lender.
+    #[ink(event)]
+    pub struct Withdraw {
+        #[ink(topic)]
+        token: AccountId,
+        #[ink(topic)]
+        provider: AccountId,
+        amount: u128,
+        shares: u128,
+    }
+
+    /// Emitted when the owner allows or disallows a receiver to reenter the lender.
+    #[ink(event)]
+    pub struct ReentryPolicyUpdated {
+        #[ink(topic)]
+        receiver: AccountId,
+        allowed: bool,
+    }
+
+    /// Emitted when the owner sets or clears the guardian.
+    #[ink(event)]
+    pub struct GuardianUpdated {
+        #[ink(topic)]
+        guardian: Option<AccountId>,
+    }
+
+    /// Emitted when lending is paused, for `token` only or for every token if `None`.
+    #[ink(event)]
+    pub struct Paused {
+        #[ink(topic)]
+        token: Option<AccountId>,
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Emitted when lending is unpaused, for `token` only or for every token if `None`.
+    #[ink(event)]
+    pub struct Unpaused {
+        #[ink(topic)]
+        token: Option<AccountId>,
+        #[ink(topic)]
+        account: AccountId,
     }
 
     impl IERC3156FlashLender for FlashLender {
         /// Loan `amount` tokens to `receiver`, and take them back plus a `flashFee` after the callback.
         ///
+        /// The borrower may repay by transferring the tokens back during the callback
+        /// or by approving the lender, which pulls whatever is still missing.
+        ///
         /// ## Params:
         /// - `receiver`: The contract receiving the tokens.  
         ///   Must implement the `onFlashLoan(address user, uint256 amount, uint256 fee, bytes calldata)` interface.
@@ -29,19 +209,25 @@ mod flash_lender {
         /// - `bool`: True if the flash loan succeeds.
         #[ink(message)]
         fn flash_loan(
-            &self,
+            &mut self,
             receiver: AccountId,
             token: AccountId,
             amount: u128,
             data: Vec<u8>,
         ) -> Result<bool> {
-            self.supported_tokens
+            Self::_ensure_erc20(token)?;
+            let fee_schedule = self
+                .supported_tokens
                 .get(token)
                 .ok_or(Error::UnsupportedCurrency)?;
-            let fee = self._flash_fee(self.fee, amount);
+            let fee = self._flash_fee(&fee_schedule, amount);
+            self._ensure_not_paused(token)?;
+            self._lock(token)?;
+            let balance_before = self._call_erc20_balance_of(token, self.env().account_id());
             self._call_erc20_transfer(receiver, token, amount)
-                .map_err(|e| Error::ERC20Error(e))?;
+                .map_err(Error::ERC20Error)?;
             let callback_result = self._call_ierc3156_flash_borrower_callback(
+                receiver,
                 self.env().caller(),
                 token,
                 amount,
@@ -55,8 +241,16 @@ mod flash_lender {
             {
                 return Err(Error::CallbackFailed);
             }
-            self._call_erc20_transfer_from(self.env().account_id(), receiver, token, amount, fee)
-                .map_err(|e: ierc20::Error| Error::ERC20Error(e))?;
+            self._collect_repayment(receiver, token, balance_before, fee)?;
+            self._accrue_fee(token, balance_before, fee)?;
+            self.active_loans.remove(token);
+            self.env().emit_event(FlashLoan {
+                receiver,
+                initiator: self.env().caller(),
+                token,
+                amount,
+                fee,
+            });
             Ok(true)
         }
 
@@ -70,10 +264,11 @@ mod flash_lender {
         /// - `u128`: The fee to be charged on top of the returned principal.
         #[ink(message)]
         fn flash_fee(&self, token: AccountId, amount: u128) -> Result<u128> {
-            self.supported_tokens
+            let fee_schedule = self
+                .supported_tokens
                 .get(token)
                 .ok_or(Error::UnsupportedCurrency)?;
-            Ok(self._flash_fee(self.fee, amount))
+            Ok(self._flash_fee(&fee_schedule, amount))
         }
 
         /// The amount of currency available to be lent.
@@ -82,18 +277,246 @@ mod flash_lender {
         /// - `token`: The loan currency.
         ///
         /// ## Returns:
-        /// - `u128`: The amount of `token` that can be borrowed.
+        /// - `u128`: The amount of `token` that can be borrowed, 0 while paused.
         #[ink(message)]
         fn max_flash_loan(&self, token: AccountId) -> Result<u128> {
-            let token_exists = self
+            if !self.supported_tokens.contains(token) {
+                return Err(Error::UnsupportedCurrency);
+            }
+            if self.is_paused(token) {
+                return Ok(0);
+            }
+            Ok(self._reserve(token))
+        }
+    }
+
+    impl IERC3156BatchFlashLender for FlashLender {
+        /// Loan `amounts` of `tokens` to `receiver`, and take them back plus the
+        /// `batchFlashFee` of every leg after the callback.
+        ///
+        /// Every leg is repaid before returning, so the loan fails as a whole if
+        /// any of them cannot be collected.
+        ///
+        /// ## Params:
+        /// - `receiver`: The contract receiving the tokens.  
+        ///   Must implement the `IERC3156BatchFlashBorrower` interface.
+        /// - `tokens`: The loan currencies.
+        /// - `amounts`: The amount of each token lent, matching `tokens` by position.
+        /// - `data`: A data parameter to be passed on to the `receiver` for any custom use.
+        ///
+        /// ## Returns:
+        /// - `bool`: True if the flash loan succeeds.
+        #[ink(message)]
+        fn batch_flash_loan(
+            &mut self,
+            receiver: AccountId,
+            tokens: Vec<AccountId>,
+            amounts: Vec<u128>,
+            data: Vec<u8>,
+        ) -> Result<bool> {
+            let fees = self.batch_flash_fee(tokens.clone(), amounts.clone())?;
+            for token in &tokens {
+                Self::_ensure_erc20(*token)?;
+                self._ensure_not_paused(*token)?;
+            }
+            // Legs of the same token are repaid together, against a single balance.
+            let mut repayments: Vec<(AccountId, u128, u128)> = Vec::new();
+            for (token, fee) in tokens.iter().zip(&fees) {
+                match repayments.iter_mut().find(|(t, _, _)| t == token) {
+                    Some((_, _, owed_fee)) => *owed_fee += fee,
+                    None => repayments.push((
+                        *token,
+                        self._call_erc20_balance_of(*token, self.env().account_id()),
+                        *fee,
+                    )),
+                }
+            }
+            for (token, _, _) in &repayments {
+                self._lock(*token)?;
+            }
+            for (token, amount) in tokens.iter().zip(&amounts) {
+                self._call_erc20_transfer(receiver, *token, *amount)
+                    .map_err(Error::ERC20Error)?;
+            }
+            let callback_result = self._call_ierc3156_batch_flash_borrower_callback(
+                receiver,
+                self.env().caller(),
+                tokens.clone(),
+                amounts.clone(),
+                fees.clone(),
+                data,
+            )?;
+            if callback_result
+                != self
+                    .env()
+                    .hash_bytes::<Keccak256>(b"ERC3156BatchFlashBorrower.onBatchFlashLoan")
+            {
+                return Err(Error::CallbackFailed);
+            }
+            for (token, balance_before, fee) in repayments {
+                self._collect_repayment(receiver, token, balance_before, fee)?;
+                self._accrue_fee(token, balance_before, fee)?;
+                self.active_loans.remove(token);
+            }
+            for ((token, amount), fee) in tokens.into_iter().zip(amounts).zip(fees) {
+                self.env().emit_event(FlashLoan {
+                    receiver,
+                    initiator: self.env().caller(),
+                    token,
+                    amount,
+                    fee,
+                });
+            }
+            Ok(true)
+        }
+
+        /// The fees to be charged for a given batch loan.
+        ///
+        /// ## Params:
+        /// - `tokens`: The loan currencies.
+        /// - `amounts`: The amount of each token lent, matching `tokens` by position.
+        ///
+        /// ## Returns:
+        /// - `Vec<u128>`: The fee of each leg, to be charged on top of the returned principal.
+        #[ink(message)]
+        fn batch_flash_fee(&self, tokens: Vec<AccountId>, amounts: Vec<u128>) -> Result<Vec<u128>> {
+            if tokens.len() != amounts.len() {
+                return Err(Error::InconsistentBatchLength);
+            }
+            tokens
+                .into_iter()
+                .zip(amounts)
+                .map(|(token, amount)| self.flash_fee(token, amount))
+                .collect()
+        }
+    }
+
+    impl IERC3156NativeFlashLender for FlashLender {
+        /// Loan `amount` of native balance to `receiver`, and check that it was sent
+        /// back plus a `flashFee` after the callback.
+        ///
+        /// The native balance is lent when `NATIVE_TOKEN` is supported, with its fee
+        /// schedule and pause state. The loan is attached as the value of the
+        /// callback. The lender cannot pull native balance, so `receiver` must
+        /// transfer the repayment during the callback.
+        ///
+        /// ## Params:
+        /// - `receiver`: The contract receiving the native balance.  
+        ///   Must implement the `IERC3156NativeFlashBorrower` interface.
+        /// - `amount`: The amount of native balance lent.
+        /// - `data`: A data parameter to be passed on to the `receiver` for any custom use.
+        ///
+        /// ## Returns:
+        /// - `bool`: True if the flash loan succeeds.
+        #[ink(message)]
+        fn native_flash_loan(
+            &mut self,
+            receiver: AccountId,
+            amount: u128,
+            data: Vec<u8>,
+        ) -> Result<bool> {
+            let fee_schedule = self
                 .supported_tokens
-                .get(token)
+                .get(NATIVE_TOKEN)
                 .ok_or(Error::UnsupportedCurrency)?;
-            if token_exists {
-                Ok(self._call_erc20_balance_of(token, self.env().caller()))
-            } else {
-                Ok(0)
+            let fee = self._flash_fee(&fee_schedule, amount);
+            self._ensure_not_paused(NATIVE_TOKEN)?;
+            self._lock(NATIVE_TOKEN)?;
+            let balance_before = self._reserve(NATIVE_TOKEN);
+            let needed = balance_before.checked_add(fee).ok_or(Error::Overflow)?;
+            let callback_result = self._call_ierc3156_native_flash_borrower_callback(
+                receiver,
+                self.env().caller(),
+                amount,
+                fee,
+                data,
+            )?;
+            if callback_result
+                != self
+                    .env()
+                    .hash_bytes::<Keccak256>(b"ERC3156NativeFlashBorrower.onNativeFlashLoan")
+            {
+                return Err(Error::CallbackFailed);
+            }
+            let balance = self._reserve(NATIVE_TOKEN);
+            if balance < needed {
+                return Err(Error::RepaymentShortfall { balance, needed });
+            }
+            self._accrue_fee(NATIVE_TOKEN, balance_before, fee)?;
+            self.active_loans.remove(NATIVE_TOKEN);
+            self.env().emit_event(FlashLoan {
+                receiver,
+                initiator: self.env().caller(),
+                token: NATIVE_TOKEN,
+                amount,
+                fee,
+            });
+            Ok(true)
+        }
+    }
+
+    impl IERC7399FlashLender for FlashLender {
+        /// Loan `amount` of `asset` to `loan_receiver`, then call `callback_selector` on
+        /// `callback`, which must transfer `amount` plus the fee to the lender.
+        ///
+        /// The loan draws on the same liquidity as `flash_loan`. Unlike it, the lender
+        /// never pulls the repayment: its balance must have grown by the fee once the
+        /// callback returns.
+        ///
+        /// ## Params:
+        /// - `loan_receiver`: The account receiving the tokens.
+        /// - `asset`: The loan currency.
+        /// - `amount`: The amount of tokens lent.
+        /// - `data`: A data parameter to be passed on to the callback for any custom use.
+        /// - `callback`: The contract called once the tokens are lent.
+        /// - `callback_selector`: The selector of the callback message.
+        ///
+        /// ## Returns:
+        /// - `Vec<u8>`: The data returned by the callback.
+        #[ink(message)]
+        fn flash(
+            &mut self,
+            loan_receiver: AccountId,
+            asset: AccountId,
+            amount: u128,
+            data: Vec<u8>,
+            callback: AccountId,
+            callback_selector: [u8; 4],
+        ) -> Result<Vec<u8>> {
+            Self::_ensure_erc20(asset)?;
+            let fee_schedule = self
+                .supported_tokens
+                .get(asset)
+                .ok_or(Error::UnsupportedCurrency)?;
+            let fee = self._flash_fee(&fee_schedule, amount);
+            self._ensure_not_paused(asset)?;
+            self._lock(asset)?;
+            let balance_before = self._call_erc20_balance_of(asset, self.env().account_id());
+            let needed = balance_before.checked_add(fee).ok_or(Error::Overflow)?;
+            self._call_erc20_transfer(loan_receiver, asset, amount)
+                .map_err(Error::ERC20Error)?;
+            let result = self._call_ierc7399_callback(
+                (callback, callback_selector),
+                self.env().caller(),
+                asset,
+                amount,
+                fee,
+                data,
+            )?;
+            let balance = self._call_erc20_balance_of(asset, self.env().account_id());
+            if balance < needed {
+                return Err(Error::RepaymentShortfall { balance, needed });
             }
+            self._accrue_fee(asset, balance_before, fee)?;
+            self.active_loans.remove(asset);
+            self.env().emit_event(FlashLoan {
+                receiver: loan_receiver,
+                initiator: self.env().caller(),
+                token: asset,
+                amount,
+                fee,
+            });
+            Ok(result)
         }
     }
 
@@ -103,29 +526,587 @@ mod flash_lender {
         /// ## Params:
         /// - `supportedTokens`: Token contracts supported for flash lending.
         /// - `fee`: The percentage of the loan `amount` that needs to be repaid,
-        ///   in addition to `amount`. (1 == 0.01%).
+        ///   in addition to `amount`. (1 == 0.01%). Applied as a flat
+        ///   [`FeeSchedule`] to every token in `supportedTokens`.
         #[ink(constructor)]
         pub fn new(_supported_tokens: Vec<AccountId>, fee: u128) -> Self {
             let mut supported_tokens = Mapping::default();
             for token in _supported_tokens {
-                supported_tokens.insert(&token, &true);
+                supported_tokens.insert(token, &FeeSchedule::flat(fee));
+                Self::env().emit_event(TokenSupported { token });
             }
             Self {
                 supported_tokens,
-                fee,
+                owner: Self::env().caller(),
+                pending_owner: None,
+                total_shares: Mapping::default(),
+                shares: Mapping::default(),
+                lp_assets: Mapping::default(),
+                active_loans: Mapping::default(),
+                reentrant_receivers: Mapping::default(),
+                guardian: None,
+                paused: false,
+                paused_tokens: Mapping::default(),
+            }
+        }
+
+        /// Returns the current owner.
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// Returns the account nominated to become the next owner, if any.
+        #[ink(message)]
+        pub fn pending_owner(&self) -> Option<AccountId> {
+            self.pending_owner
+        }
+
+        /// Returns the fee schedule of `token`, if supported.
+        #[ink(message)]
+        pub fn fee_schedule(&self, token: AccountId) -> Option<FeeSchedule> {
+            self.supported_tokens.get(token)
+        }
+
+        /// Nominates `new_owner` as the next owner. The ownership is not
+        /// transferred until `new_owner` calls `accept_ownership`.
+        ///
+        /// ## Params:
+        /// - `new_owner`: The account to nominate.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.pending_owner = Some(new_owner);
+            self.env().emit_event(OwnershipTransferStarted {
+                previous_owner: self.owner,
+                new_owner,
+            });
+            Ok(())
+        }
+
+        /// Accepts the ownership nominated through `transfer_ownership`.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the pending owner.
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            if self.pending_owner != Some(caller) {
+                return Err(Error::Unauthorized);
+            }
+            let previous_owner = self.owner;
+            self.owner = caller;
+            self.pending_owner = None;
+            self.env().emit_event(OwnershipTransferred {
+                previous_owner,
+                new_owner: caller,
+            });
+            Ok(())
+        }
+
+        /// Adds `token` to the tokens supported for flash lending.
+        ///
+        /// The fees of a supported token are changed through `set_fee`.
+        ///
+        /// ## Params:
+        /// - `token`: The token contract to support.
+        /// - `fee_schedule`: The fees charged on loans of `token`.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        /// - `AlreadySupported` if `token` is already supported.
+        #[ink(message)]
+        pub fn add_supported_token(
+            &mut self,
+            token: AccountId,
+            fee_schedule: FeeSchedule,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            if self.supported_tokens.contains(token) {
+                return Err(Error::AlreadySupported);
+            }
+            self.supported_tokens.insert(token, &fee_schedule);
+            self.env().emit_event(TokenSupported { token });
+            Ok(())
+        }
+
+        /// Removes `token` from the tokens supported for flash lending.
+        ///
+        /// ## Params:
+        /// - `token`: The token contract to remove.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        /// - `UnsupportedCurrency` if `token` is not supported.
+        #[ink(message)]
+        pub fn remove_supported_token(&mut self, token: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.supported_tokens
+                .take(token)
+                .ok_or(Error::UnsupportedCurrency)?;
+            self.env().emit_event(TokenRemoved { token });
+            Ok(())
+        }
+
+        /// Sets the fee schedule of a supported token.
+        ///
+        /// ## Params:
+        /// - `token`: The token contract.
+        /// - `fee_schedule`: The new fees charged on loans of `token`.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        /// - `UnsupportedCurrency` if `token` is not supported.
+        #[ink(message)]
+        pub fn set_fee(&mut self, token: AccountId, fee_schedule: FeeSchedule) -> Result<()> {
+            self.ensure_owner()?;
+            let old_fee = self
+                .supported_tokens
+                .get(token)
+                .ok_or(Error::UnsupportedCurrency)?;
+            self.supported_tokens.insert(token, &fee_schedule);
+            self.env().emit_event(FeeUpdated {
+                token,
+                old_fee,
+                new_fee: fee_schedule,
+            });
+            Ok(())
+        }
+
+        /// Returns whether `receiver` may call back into the lender during its callback.
+        #[ink(message)]
+        pub fn is_reentry_allowed(&self, receiver: AccountId) -> bool {
+            self.reentrant_receivers.contains(receiver)
+        }
+
+        /// Allows or disallows `receiver` to call back into the lender during its
+        /// callback, e.g. to compose loans of other tokens. A nested loan of a token
+        /// that is already being lent is rejected either way.
+        ///
+        /// ## Params:
+        /// - `receiver`: The trusted strategy contract.
+        /// - `allowed`: Whether reentry is allowed.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        #[ink(message)]
+        pub fn set_reentry_allowed(&mut self, receiver: AccountId, allowed: bool) -> Result<()> {
+            self.ensure_owner()?;
+            if allowed {
+                self.reentrant_receivers.insert(receiver, &());
+            } else {
+                self.reentrant_receivers.remove(receiver);
+            }
+            self.env()
+                .emit_event(ReentryPolicyUpdated { receiver, allowed });
+            Ok(())
+        }
+
+        /// Returns the guardian, if any.
+        #[ink(message)]
+        pub fn guardian(&self) -> Option<AccountId> {
+            self.guardian
+        }
+
+        /// Returns whether lending of `token` is paused, either globally or for
+        /// `token` only.
+        #[ink(message)]
+        pub fn is_paused(&self, token: AccountId) -> bool {
+            self.paused || self.paused_tokens.contains(token)
+        }
+
+        /// Sets the account allowed to pause lending besides the owner, or
+        /// clears it with `None`.
+        ///
+        /// ## Params:
+        /// - `guardian`: The new guardian.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        #[ink(message)]
+        pub fn set_guardian(&mut self, guardian: Option<AccountId>) -> Result<()> {
+            self.ensure_owner()?;
+            self.guardian = guardian;
+            self.env().emit_event(GuardianUpdated { guardian });
+            Ok(())
+        }
+
+        /// Pauses lending of every token.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is neither the owner nor the guardian.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<()> {
+            self.ensure_owner_or_guardian()?;
+            self.paused = true;
+            self.env().emit_event(Paused {
+                token: None,
+                account: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        /// Unpauses lending of every token. Tokens paused individually stay paused.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<()> {
+            self.ensure_owner()?;
+            self.paused = false;
+            self.env().emit_event(Unpaused {
+                token: None,
+                account: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        /// Pauses lending of `token`.
+        ///
+        /// ## Params:
+        /// - `token`: The token to pause.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is neither the owner nor the guardian.
+        #[ink(message)]
+        pub fn pause_token(&mut self, token: AccountId) -> Result<()> {
+            self.ensure_owner_or_guardian()?;
+            self.paused_tokens.insert(token, &());
+            self.env().emit_event(Paused {
+                token: Some(token),
+                account: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        /// Unpauses lending of `token`.
+        ///
+        /// ## Params:
+        /// - `token`: The token to unpause.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        #[ink(message)]
+        pub fn unpause_token(&mut self, token: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            self.paused_tokens.remove(token);
+            self.env().emit_event(Unpaused {
+                token: Some(token),
+                account: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        /// Deposits `amount` of `token` into the lender, minting liquidity provider
+        /// shares to the caller. The caller must have approved the lender to
+        /// spend `amount`, or attach `amount` to the call for `NATIVE_TOKEN`.
+        ///
+        /// ## Params:
+        /// - `token`: The supported token to deposit.
+        /// - `amount`: The amount of tokens deposited.
+        ///
+        /// ## Returns:
+        /// - `u128`: The amount of shares minted.
+        ///
+        /// ## Errors:
+        /// - `InvalidTransferredValue` if the attached value is not `amount` for
+        ///   `NATIVE_TOKEN`, or not zero for other tokens.
+        #[ink(message, payable)]
+        pub fn deposit(&mut self, token: AccountId, amount: u128) -> Result<u128> {
+            if !self.supported_tokens.contains(token) {
+                return Err(Error::UnsupportedCurrency);
+            }
+            self._ensure_idle(token)?;
+            let shares = self.preview_deposit(token, amount)?;
+            if shares == 0 {
+                return Err(Error::ZeroShares);
+            }
+            let provider = self.env().caller();
+            let expected_value = if token == NATIVE_TOKEN { amount } else { 0 };
+            if self.env().transferred_value() != expected_value {
+                return Err(Error::InvalidTransferredValue);
+            }
+            if token != NATIVE_TOKEN {
+                self._call_erc20_transfer_from(self.env().account_id(), provider, token, amount, 0)
+                    .map_err(Error::ERC20Error)?;
+            }
+            self.lp_assets
+                .insert(token, &(self.total_assets(token) + amount));
+            self.total_shares
+                .insert(token, &(self.total_shares(token) + shares));
+            self.shares.insert(
+                (token, provider),
+                &(self.shares_of(token, provider) + shares),
+            );
+            self.env().emit_event(Deposit {
+                token,
+                provider,
+                amount,
+                shares,
+            });
+            Ok(shares)
+        }
+
+        /// Burns `shares` of the caller and sends back their value in `token`,
+        /// including the flash fees accrued since the deposit.
+        ///
+        /// ## Params:
+        /// - `token`: The token to withdraw.
+        /// - `shares`: The amount of shares burned.
+        ///
+        /// ## Returns:
+        /// - `u128`: The amount of tokens withdrawn.
+        #[ink(message)]
+        pub fn withdraw(&mut self, token: AccountId, shares: u128) -> Result<u128> {
+            self._ensure_idle(token)?;
+            let provider = self.env().caller();
+            let provider_shares = self.shares_of(token, provider);
+            if provider_shares < shares {
+                return Err(Error::InsufficientShares);
+            }
+            let amount = self.preview_withdraw(token, shares)?;
+            if amount == 0 {
+                return Err(Error::ZeroShares);
+            }
+            self.lp_assets
+                .insert(token, &(self.total_assets(token) - amount));
+            self.total_shares
+                .insert(token, &(self.total_shares(token) - shares));
+            self.shares
+                .insert((token, provider), &(provider_shares - shares));
+            if token == NATIVE_TOKEN {
+                self.env()
+                    .transfer(provider, amount)
+                    .map_err(|_| Error::TransferFailed)?;
+            } else {
+                self._call_erc20_transfer(provider, token, amount)
+                    .map_err(Error::ERC20Error)?;
+            }
+            self.env().emit_event(Withdraw {
+                token,
+                provider,
+                amount,
+                shares,
+            });
+            Ok(amount)
+        }
+
+        /// Returns the total liquidity provider shares issued for `token`.
+        #[ink(message)]
+        pub fn total_shares(&self, token: AccountId) -> u128 {
+            self.total_shares.get(token).unwrap_or_default()
+        }
+
+        /// Returns the liquidity provider shares of `provider` for `token`.
+        #[ink(message)]
+        pub fn shares_of(&self, token: AccountId, provider: AccountId) -> u128 {
+            self.shares.get((token, provider)).unwrap_or_default()
+        }
+
+        /// Returns the tokens owned by the liquidity providers of `token`: their
+        /// deposits plus the flash fees earned on them. Tokens sent to the lender
+        /// by other means are not included.
+        #[ink(message)]
+        pub fn total_assets(&self, token: AccountId) -> u128 {
+            self.lp_assets.get(token).unwrap_or_default()
+        }
+
+        /// Returns the value of one share in `token`, scaled by
+        /// [`SHARE_PRICE_PRECISION`].
+        #[ink(message)]
+        pub fn share_price(&self, token: AccountId) -> Result<u128> {
+            Self::mul_div(
+                self.total_assets(token) + VIRTUAL_LIQUIDITY,
+                SHARE_PRICE_PRECISION,
+                self.total_shares(token) + VIRTUAL_LIQUIDITY,
+            )
+        }
+
+        /// Returns the shares that a deposit of `amount` of `token` would mint,
+        /// rounded down.
+        #[ink(message)]
+        pub fn preview_deposit(&self, token: AccountId, amount: u128) -> Result<u128> {
+            Self::mul_div(
+                amount,
+                self.total_shares(token) + VIRTUAL_LIQUIDITY,
+                self.total_assets(token) + VIRTUAL_LIQUIDITY,
+            )
+        }
+
+        /// Returns the amount of `token` that burning `shares` would withdraw,
+        /// rounded down.
+        #[ink(message)]
+        pub fn preview_withdraw(&self, token: AccountId, shares: u128) -> Result<u128> {
+            let total_assets = self.total_assets(token);
+            let amount = Self::mul_div(
+                shares,
+                total_assets + VIRTUAL_LIQUIDITY,
+                self.total_shares(token) + VIRTUAL_LIQUIDITY,
+            )?;
+            Ok(amount.min(total_assets))
+        }
+
+        /// Returns `UnsupportedCurrency` for `NATIVE_TOKEN`, which is only lent by
+        /// `native_flash_loan`.
+        fn _ensure_erc20(token: AccountId) -> Result<()> {
+            if token == NATIVE_TOKEN {
+                return Err(Error::UnsupportedCurrency);
+            }
+            Ok(())
+        }
+
+        /// Returns the lender balance of `token`, leaving out the native value
+        /// attached to the current call.
+        fn _reserve(&self, token: AccountId) -> u128 {
+            if token == NATIVE_TOKEN {
+                return self
+                    .env()
+                    .balance()
+                    .saturating_sub(self.env().transferred_value());
+            }
+            self._call_erc20_balance_of(token, self.env().account_id())
+        }
+
+        /// Returns `Paused` if lending of `token` is paused.
+        fn _ensure_not_paused(&self, token: AccountId) -> Result<()> {
+            if self.is_paused(token) {
+                return Err(Error::Paused);
+            }
+            Ok(())
+        }
+
+        /// Marks a loan of `token` as in progress.
+        ///
+        /// ## Errors:
+        /// - `ReentrantLoan` if a loan of `token` is already in progress.
+        fn _lock(&mut self, token: AccountId) -> Result<()> {
+            self._ensure_idle(token)?;
+            self.active_loans.insert(token, &());
+            Ok(())
+        }
+
+        /// Returns `ReentrantLoan` if a loan of `token` is in progress.
+        fn _ensure_idle(&self, token: AccountId) -> Result<()> {
+            if self.active_loans.contains(token) {
+                return Err(Error::ReentrantLoan);
+            }
+            Ok(())
+        }
+
+        /// Call flags of the callback to `receiver`, allowing it to reenter the
+        /// lender only if trusted by the owner.
+        fn _callback_flags(&self, receiver: AccountId) -> CallFlags {
+            if self.is_reentry_allowed(receiver) {
+                CallFlags::ALLOW_REENTRY
+            } else {
+                CallFlags::empty()
+            }
+        }
+
+        /// Computes `a * b / c` rounding down.
+        fn mul_div(a: u128, b: u128, c: u128) -> Result<u128> {
+            a.checked_mul(b)
+                .map(|product| product / c)
+                .ok_or(Error::Overflow)
+        }
+
+        /// Returns `Unauthorized` unless the caller is the owner.
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        /// Returns `Unauthorized` unless the caller is the owner or the guardian.
+        fn ensure_owner_or_guardian(&self) -> Result<()> {
+            let caller = self.env().caller();
+            if caller != self.owner && self.guardian != Some(caller) {
+                return Err(Error::Unauthorized);
             }
+            Ok(())
         }
 
         /// Internal function returning the fee to be charged for a given loan.  
         /// No safety checks are performed.
         ///
+        /// The proportional fee is raised to `min_fee` and then limited to
+        /// `max_fee`, so the cap wins if both bounds conflict.
+        ///
         /// ## Params:
+        /// - `fee_schedule`: The fee configuration of the loan currency.
         /// - `amount`: The amount of tokens lent.
         ///
         /// ## Returns:
         /// - `u256`: The fee to be charged on top of the returned principal.
-        fn _flash_fee(&self, fee: u128, amount: u128) -> u128 {
-            amount * fee / 10000
+        fn _flash_fee(&self, fee_schedule: &FeeSchedule, amount: u128) -> u128 {
+            let fee = (amount.saturating_mul(fee_schedule.rate) / 10000).max(fee_schedule.min_fee);
+            match fee_schedule.max_fee {
+                Some(max_fee) => fee.min(max_fee),
+                None => fee,
+            }
+        }
+
+        /// Internal function crediting the liquidity providers of `token` with their
+        /// part of a repaid loan `fee`, in proportion to the lender balance they own.
+        ///
+        /// ## Params:
+        /// - `token`: The loan currency.
+        /// - `balance_before`: The lender balance of `token` before the loan.
+        /// - `fee`: The fee repaid on top of the principal.
+        fn _accrue_fee(&mut self, token: AccountId, balance_before: u128, fee: u128) -> Result<()> {
+            let total_assets = self.total_assets(token);
+            if total_assets == 0 || balance_before == 0 {
+                return Ok(());
+            }
+            let lp_fee = Self::mul_div(fee, total_assets.min(balance_before), balance_before)?;
+            self.lp_assets.insert(token, &(total_assets + lp_fee));
+            Ok(())
+        }
+
+        /// Internal function verifying that the lender got back the principal plus
+        /// `fee` of a loan.
+        ///
+        /// Tokens already pushed by the borrower count towards the repayment; any
+        /// remainder is pulled from `receiver` through its allowance.
+        ///
+        /// ## Params:
+        /// - `receiver`: The borrower repaying the loan.
+        /// - `token`: AccountId of the ERC20 contract.
+        /// - `balance_before`: The lender balance of `token` before the loan.
+        /// - `fee`: The fee owed on top of the principal.
+        ///
+        /// ## Errors:
+        /// - `RepaymentShortfall` if the final balance is lower than `balance_before + fee`,
+        ///   including when the remainder cannot be pulled.
+        fn _collect_repayment(
+            &self,
+            receiver: AccountId,
+            token: AccountId,
+            balance_before: u128,
+            fee: u128,
+        ) -> Result<()> {
+            let needed = balance_before.checked_add(fee).ok_or(Error::Overflow)?;
+            let balance = self._call_erc20_balance_of(token, self.env().account_id());
+            if balance >= needed {
+                return Ok(());
+            }
+            // A failed pull leaves the balance short, which is reported below.
+            let _ = self._call_erc20_transfer_from(
+                self.env().account_id(),
+                receiver,
+                token,
+                needed - balance,
+                0,
+            );
+            let balance = self._call_erc20_balance_of(token, self.env().account_id());
+            if balance < needed {
+                return Err(Error::RepaymentShortfall { balance, needed });
+            }
+            Ok(())
         }
 
         /// Calls the ERC20 `balance_of` function on a given token contract.
@@ -192,6 +1173,7 @@ mod flash_lender {
         /// tokens and must execute its logic before repayment.
         ///
         /// ## Params:
+        /// - `receiver`: The `IERC3156FlashBorrower` contract receiving the loan.
         /// - `sender`: who initiated tx.
         /// - `token`: AccountId of the ERC20 token contract used in the loan.
         /// - `amount`: Principal amount borrowed.
@@ -202,16 +1184,1310 @@ mod flash_lender {
         /// - A boolean indicating whether the callback succeeded.
         fn _call_ierc3156_flash_borrower_callback(
             &self,
+            receiver: AccountId,
             sender: AccountId,
             token: AccountId,
             amount: u128,
             fee: u128,
             data: Vec<u8>,
         ) -> Result<[u8; 32]> {
-            let borrower: ink::contract_ref!(IERC3156FlashBorrower) = token.into();
+            let borrower: ink::contract_ref!(IERC3156FlashBorrower) = receiver.into();
             borrower
+                .call()
                 .on_flash_loan(sender, token, amount, fee, data)
-                .map_err(|e| Error::ERC3156BorrowerLoanError)
+                .call_flags(self._callback_flags(receiver))
+                .invoke()
+                .map_err(|_| Error::ERC3156BorrowerLoanError)
+        }
+
+        /// Calls the `on_batch_flash_loan` callback on an `IERC3156BatchFlashBorrower` contract.
+        ///
+        /// ## Params:
+        /// - `receiver`: The `IERC3156BatchFlashBorrower` contract receiving the loan.
+        /// - `sender`: who initiated tx.
+        /// - `tokens`: AccountIds of the ERC20 token contracts used in the loan.
+        /// - `amounts`: Principal amount borrowed of each token.
+        /// - `fees`: Additional fee required for the repayment of each token.
+        /// - `data`: Arbitrary bytes data passed through to the borrower.
+        ///
+        /// ## Returns:
+        /// - The hash returned by the borrower.
+        fn _call_ierc3156_batch_flash_borrower_callback(
+            &self,
+            receiver: AccountId,
+            sender: AccountId,
+            tokens: Vec<AccountId>,
+            amounts: Vec<u128>,
+            fees: Vec<u128>,
+            data: Vec<u8>,
+        ) -> Result<[u8; 32]> {
+            let borrower: ink::contract_ref!(IERC3156BatchFlashBorrower) = receiver.into();
+            borrower
+                .call()
+                .on_batch_flash_loan(sender, tokens, amounts, fees, data)
+                .call_flags(self._callback_flags(receiver))
+                .invoke()
+                .map_err(|_| Error::ERC3156BorrowerLoanError)
+        }
+
+        /// Calls the `on_native_flash_loan` callback on an `IERC3156NativeFlashBorrower`
+        /// contract, sending it `amount` of native balance along with the call.
+        ///
+        /// ## Params:
+        /// - `receiver`: The `IERC3156NativeFlashBorrower` contract receiving the loan.
+        /// - `sender`: who initiated tx.
+        /// - `amount`: Principal amount borrowed.
+        /// - `fee`: Additional fee required for repayment.
+        /// - `data`: Arbitrary bytes data passed through to the borrower.
+        ///
+        /// ## Returns:
+        /// - The hash returned by the borrower.
+        fn _call_ierc3156_native_flash_borrower_callback(
+            &self,
+            receiver: AccountId,
+            sender: AccountId,
+            amount: u128,
+            fee: u128,
+            data: Vec<u8>,
+        ) -> Result<[u8; 32]> {
+            let mut borrower: ink::contract_ref!(IERC3156NativeFlashBorrower) = receiver.into();
+            borrower
+                .call_mut()
+                .on_native_flash_loan(sender, amount, fee, data)
+                .transferred_value(amount)
+                .call_flags(self._callback_flags(receiver))
+                .invoke()
+                .map_err(|_| Error::ERC3156BorrowerLoanError)
+        }
+
+        /// Calls the `IERC7399` callback of a loan, naming the lender as the payment
+        /// receiver.
+        ///
+        /// ## Params:
+        /// - `callback`: The contract implementing the callback and the selector of
+        ///   the callback message.
+        /// - `sender`: who initiated tx.
+        /// - `asset`: AccountId of the ERC20 token contract used in the loan.
+        /// - `amount`: Principal amount borrowed.
+        /// - `fee`: Additional fee required for repayment.
+        /// - `data`: Arbitrary bytes data passed through to the callback.
+        ///
+        /// ## Returns:
+        /// - The data returned by the callback.
+        fn _call_ierc7399_callback(
+            &self,
+            (callback, selector): (AccountId, [u8; 4]),
+            sender: AccountId,
+            asset: AccountId,
+            amount: u128,
+            fee: u128,
+            data: Vec<u8>,
+        ) -> Result<Vec<u8>> {
+            build_call::<Environment>()
+                .call(callback)
+                .call_flags(self._callback_flags(callback))
+                .exec_input(
+                    ExecutionInput::new(Selector::new(selector))
+                        .push_arg(sender)
+                        .push_arg(self.env().account_id())
+                        .push_arg(asset)
+                        .push_arg(amount)
+                        .push_arg(fee)
+                        .push_arg(data),
+                )
+                .returns::<ierc3156::ierc3156_flash_borrower::Result<Vec<u8>>>()
+                .try_invoke()
+                .map_err(|_| Error::ERC3156BorrowerLoanError)?
+                .map_err(|_| Error::ERC3156BorrowerLoanError)?
+                .map_err(|_| Error::CallbackFailed)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink::scale::Encode;
+
+        fn accounts() -> ink::env::test::DefaultAccounts<Environment> {
+            ink::env::test::default_accounts::<Environment>()
+        }
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<Environment>(caller);
+        }
+
+        /// Returns the recorded `E` events, each with its topics after the signature.
+        fn recorded_events<E: ink::env::Event + ink::scale::Decode>() -> Vec<(E, Vec<Vec<u8>>)> {
+            let signature = E::SIGNATURE_TOPIC.expect("the event is not anonymous");
+            ink::env::test::recorded_events()
+                .filter(|event| event.topics.first().map(|t| &t[..]) == Some(&signature[..]))
+                .map(|event| {
+                    let decoded = E::decode(&mut &event.data[..]).expect("a matching event");
+                    (decoded, event.topics[1..].to_vec())
+                })
+                .collect()
+        }
+
+        #[ink::test]
+        fn supported_tokens_are_not_added_twice() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut lender = FlashLender::new(vec![accounts.django], 10);
+
+            assert_eq!(
+                lender.add_supported_token(accounts.django, FeeSchedule::flat(50)),
+                Err(Error::AlreadySupported)
+            );
+            assert_eq!(
+                lender.fee_schedule(accounts.django),
+                Some(FeeSchedule::flat(10))
+            );
+            assert_eq!(
+                lender.add_supported_token(accounts.eve, FeeSchedule::flat(50)),
+                Ok(())
+            );
+            assert_eq!(lender.flash_fee(accounts.eve, 10_000), Ok(50));
+        }
+
+        #[ink::test]
+        fn only_the_owner_transfers_ownership_and_sets_fees() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut lender = FlashLender::new(vec![accounts.django], 10);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                lender.transfer_ownership(accounts.bob),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                lender.set_fee(accounts.django, FeeSchedule::flat(50)),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(lender.pending_owner(), None);
+            assert_eq!(
+                lender.fee_schedule(accounts.django),
+                Some(FeeSchedule::flat(10))
+            );
+        }
+
+        #[ink::test]
+        fn pending_owner_accepts_ownership() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut lender = FlashLender::new(vec![accounts.django], 10);
+            assert_eq!(lender.transfer_ownership(accounts.bob), Ok(()));
+            assert_eq!(lender.owner(), accounts.alice);
+            assert_eq!(lender.pending_owner(), Some(accounts.bob));
+
+            set_caller(accounts.charlie);
+            assert_eq!(lender.accept_ownership(), Err(Error::Unauthorized));
+            assert_eq!(lender.owner(), accounts.alice);
+
+            set_caller(accounts.bob);
+            assert_eq!(lender.accept_ownership(), Ok(()));
+            assert_eq!(lender.owner(), accounts.bob);
+            assert_eq!(lender.pending_owner(), None);
+            // The new owner holds the owner rights, the previous one lost them.
+            assert_eq!(
+                lender.set_fee(accounts.django, FeeSchedule::flat(50)),
+                Ok(())
+            );
+            set_caller(accounts.alice);
+            assert_eq!(
+                lender.transfer_ownership(accounts.alice),
+                Err(Error::Unauthorized)
+            );
+        }
+
+        #[ink::test]
+        fn fees_are_only_set_on_supported_tokens() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut lender = FlashLender::new(vec![accounts.django], 10);
+
+            assert_eq!(
+                lender.set_fee(accounts.eve, FeeSchedule::flat(50)),
+                Err(Error::UnsupportedCurrency)
+            );
+            assert_eq!(lender.fee_schedule(accounts.eve), None);
+            assert_eq!(
+                lender.set_fee(accounts.django, FeeSchedule::flat(50)),
+                Ok(())
+            );
+            assert_eq!(lender.flash_fee(accounts.django, 10_000), Ok(50));
+        }
+
+        #[ink::test]
+        fn global_pause_stops_every_loan() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut lender =
+                FlashLender::new(vec![accounts.django, accounts.frank, NATIVE_TOKEN], 10);
+            assert_eq!(lender.set_guardian(Some(accounts.bob)), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(lender.pause_token(accounts.frank), Ok(()));
+            assert_eq!(lender.pause(), Ok(()));
+            assert_eq!(lender.unpause(), Err(Error::Unauthorized));
+            assert!(lender.is_paused(accounts.django));
+            assert!(lender.is_paused(NATIVE_TOKEN));
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                lender.flash_loan(accounts.charlie, accounts.django, 100, Vec::new()),
+                Err(Error::Paused)
+            );
+            assert_eq!(
+                lender.batch_flash_loan(
+                    accounts.charlie,
+                    vec![accounts.django],
+                    vec![100],
+                    Vec::new()
+                ),
+                Err(Error::Paused)
+            );
+            assert_eq!(
+                lender.flash(
+                    accounts.charlie,
+                    accounts.django,
+                    100,
+                    Vec::new(),
+                    accounts.charlie,
+                    [0; 4]
+                ),
+                Err(Error::Paused)
+            );
+            assert_eq!(
+                lender.native_flash_loan(accounts.charlie, 100, Vec::new()),
+                Err(Error::Paused)
+            );
+            assert_eq!(lender.max_flash_loan(accounts.django), Ok(0));
+            assert_eq!(lender.max_flash_loan(NATIVE_TOKEN), Ok(0));
+            assert_eq!(lender.flash_fee(accounts.django, 10_000), Ok(10));
+
+            // Lifting the global pause leaves the individually paused token paused.
+            set_caller(accounts.alice);
+            assert_eq!(lender.unpause(), Ok(()));
+            assert!(!lender.is_paused(accounts.django));
+            assert!(lender.is_paused(accounts.frank));
+            assert_eq!(lender.max_flash_loan(accounts.frank), Ok(0));
+            assert_eq!(
+                lender.flash_loan(accounts.charlie, accounts.frank, 100, Vec::new()),
+                Err(Error::Paused)
+            );
+        }
+
+        #[ink::test]
+        fn token_listing_events_are_indexed_by_token() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut lender = FlashLender::new(vec![accounts.django], 10);
+            lender
+                .add_supported_token(accounts.eve, FeeSchedule::flat(50))
+                .unwrap();
+            lender
+                .set_fee(accounts.django, FeeSchedule::flat(30))
+                .unwrap();
+            lender.remove_supported_token(accounts.eve).unwrap();
+
+            let supported = recorded_events::<TokenSupported>();
+            assert_eq!(supported.len(), 2);
+            for ((event, topics), token) in supported.iter().zip([accounts.django, accounts.eve]) {
+                assert_eq!(event.token, token);
+                assert_eq!(topics, &vec![token.encode()]);
+            }
+
+            let fee_updates = recorded_events::<FeeUpdated>();
+            assert_eq!(fee_updates.len(), 1);
+            let (event, topics) = &fee_updates[0];
+            assert_eq!(event.token, accounts.django);
+            assert_eq!(event.old_fee, FeeSchedule::flat(10));
+            assert_eq!(event.new_fee, FeeSchedule::flat(30));
+            assert_eq!(topics, &vec![accounts.django.encode()]);
+
+            let removed = recorded_events::<TokenRemoved>();
+            assert_eq!(removed.len(), 1);
+            assert_eq!(removed[0].0.token, accounts.eve);
+            assert_eq!(removed[0].1, vec![accounts.eve.encode()]);
+        }
+
+        fn deposit_native(
+            lender: &mut FlashLender,
+            provider: AccountId,
+            amount: u128,
+        ) -> Result<u128> {
+            set_caller(provider);
+            ink::env::test::set_value_transferred::<Environment>(amount);
+            let shares = lender.deposit(NATIVE_TOKEN, amount);
+            ink::env::test::set_value_transferred::<Environment>(0);
+            shares
+        }
+
+        #[ink::test]
+        fn first_deposit_mints_shares_at_par() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut lender = FlashLender::new(vec![NATIVE_TOKEN], 10);
+
+            assert_eq!(deposit_native(&mut lender, accounts.bob, 1_000), Ok(1_000));
+            assert_eq!(lender.total_assets(NATIVE_TOKEN), 1_000);
+            assert_eq!(lender.share_price(NATIVE_TOKEN), Ok(SHARE_PRICE_PRECISION));
+            assert_eq!(lender.preview_withdraw(NATIVE_TOKEN, 1_000), Ok(1_000));
+        }
+
+        #[ink::test]
+        fn donation_before_first_deposit_is_not_captured() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut lender = FlashLender::new(vec![NATIVE_TOKEN], 10);
+            // Liquidity funded by the owner, or donated to inflate the share price.
+            ink::env::test::set_account_balance::<Environment>(
+                ink::env::account_id::<Environment>(),
+                1_000_000,
+            );
+
+            assert_eq!(deposit_native(&mut lender, accounts.bob, 1), Ok(1));
+            assert_eq!(lender.preview_withdraw(NATIVE_TOKEN, 1), Ok(1));
+            assert_eq!(deposit_native(&mut lender, accounts.charlie, 500), Ok(500));
+            assert_eq!(lender.total_assets(NATIVE_TOKEN), 501);
+        }
+
+        #[ink::test]
+        fn share_rounding_favours_the_pool() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut lender = FlashLender::new(vec![NATIVE_TOKEN], 10);
+            assert_eq!(
+                deposit_native(&mut lender, accounts.bob, 1_000_000),
+                Ok(1_000_000)
+            );
+            // Fees earned by the providers of the pool.
+            lender
+                ._accrue_fee(NATIVE_TOKEN, 1_000_000, 500_000)
+                .unwrap();
+            assert_eq!(lender.total_assets(NATIVE_TOKEN), 1_500_000);
+
+            // 7 * 1_001_000 / 1_501_000 = 4.67 shares, rounded down.
+            assert_eq!(deposit_native(&mut lender, accounts.charlie, 7), Ok(4));
+            // 4 * 1_501_007 / 1_001_004 = 5.99 tokens, rounded down.
+            assert_eq!(lender.preview_withdraw(NATIVE_TOKEN, 4), Ok(5));
+            let all_shares = lender.total_shares(NATIVE_TOKEN);
+            assert!(
+                lender.preview_withdraw(NATIVE_TOKEN, all_shares).unwrap()
+                    <= lender.total_assets(NATIVE_TOKEN)
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use erc20::{Erc20, Erc20Ref};
+        use ierc3156::ierc7399_flash_borrower::ON_FLASH_SELECTOR;
+        use ink::scale::Encode;
+        use ink_e2e::events::{ContractEmitted, EventWithTopics};
+        use ink_e2e::{ChainBackend, ContractsBackend, H256};
+        use mock_receiver::{Instructions, MockReceiverRef, NestedLoan, Repayment};
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        /// Returns the `E` events emitted by `contract` during a call, each with its
+        /// topics after the signature.
+        fn emitted_events<E: ink::env::Event + ink::scale::Decode>(
+            events: Vec<EventWithTopics<ContractEmitted<Environment>>>,
+            contract: AccountId,
+        ) -> Vec<(E, Vec<H256>)> {
+            let signature = E::SIGNATURE_TOPIC.expect("the event is not anonymous");
+            events
+                .into_iter()
+                .filter(|event| event.event.contract == contract)
+                .filter(|event| event.topics.first().map(|t| t.0) == Some(signature))
+                .map(|event| {
+                    let decoded = E::decode(&mut &event.event.data[..]).expect("a matching event");
+                    (decoded, event.topics[1..].to_vec())
+                })
+                .collect()
+        }
+
+        /// Returns the topic indexing `account`.
+        fn topic(account: AccountId) -> H256 {
+            H256::from_slice(&account.encode())
+        }
+
+        #[ink_e2e::test]
+        async fn max_flash_loan_reports_lender_balance<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let lender_call = lender.call_builder::<FlashLender>();
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(lender.account_id, 250_000),
+                )
+                .submit()
+                .await
+                .expect("transfer failed");
+
+            let max_flash_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.max_flash_loan(erc20.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(max_flash_loan.return_value(), Ok(250_000));
+
+            let flash_fee = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash_fee(erc20.account_id, 100_000),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(flash_fee.return_value(), Ok(100));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn borrower_repays_by_transfer_without_approval<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut lender_call = lender.call_builder::<FlashLender>();
+
+            let mut receiver_constructor = MockReceiverRef::new(lender.account_id);
+            let receiver = client
+                .instantiate(
+                    "mock_receiver",
+                    &ink_e2e::alice(),
+                    &mut receiver_constructor,
+                )
+                .submit()
+                .await
+                .expect("mock_receiver instantiate failed");
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(lender.account_id, 250_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(receiver.account_id, 1_000),
+                )
+                .submit()
+                .await
+                .expect("receiver funding failed");
+
+            let flash_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash_loan(
+                        receiver.account_id,
+                        erc20.account_id,
+                        100_000,
+                        Instructions::from(Repayment::Transfer).encode(),
+                    ),
+                )
+                .submit()
+                .await
+                .expect("flash_loan failed");
+            let flash_loans = emitted_events::<FlashLoan>(
+                flash_loan.contract_emitted_events()?,
+                lender.account_id,
+            );
+            assert_eq!(flash_loan.return_value(), Ok(true));
+            assert_eq!(flash_loans.len(), 1);
+            let (event, topics) = &flash_loans[0];
+            assert_eq!((event.amount, event.fee), (100_000, 100));
+            assert_eq!(
+                topics,
+                &vec![
+                    topic(receiver.account_id),
+                    topic(ink_e2e::account_id(ink_e2e::AccountKeyring::Alice)),
+                    topic(erc20.account_id),
+                ]
+            );
+
+            let lender_balance = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(lender.account_id))
+                .dry_run()
+                .await?;
+            assert_eq!(lender_balance.return_value(), 250_100);
+            let receiver_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.balance_of(receiver.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(receiver_balance.return_value(), 900);
+            let allowance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.allowance(receiver.account_id, lender.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(allowance.return_value(), 0);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn short_repayment_is_rejected<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut lender_call = lender.call_builder::<FlashLender>();
+
+            let mut receiver_constructor = MockReceiverRef::new(lender.account_id);
+            let receiver = client
+                .instantiate(
+                    "mock_receiver",
+                    &ink_e2e::alice(),
+                    &mut receiver_constructor,
+                )
+                .submit()
+                .await
+                .expect("mock_receiver instantiate failed");
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(lender.account_id, 250_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(receiver.account_id, 1_000),
+                )
+                .submit()
+                .await
+                .expect("receiver funding failed");
+
+            // The receiver pushes back one token less than owed and approves nothing.
+            let flash_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash_loan(
+                        receiver.account_id,
+                        erc20.account_id,
+                        100_000,
+                        Instructions::from(Repayment::Short(1)).encode(),
+                    ),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(
+                flash_loan.return_value(),
+                Err(Error::RepaymentShortfall {
+                    balance: 250_099,
+                    needed: 250_100,
+                })
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn reentrant_receiver_cannot_nest_a_loan_of_the_same_token<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+            let other_erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut other_erc20_call = other_erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor =
+                FlashLenderRef::new(vec![erc20.account_id, other_erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut lender_call = lender.call_builder::<FlashLender>();
+
+            let mut receiver_constructor = MockReceiverRef::new(lender.account_id);
+            let receiver = client
+                .instantiate(
+                    "mock_receiver",
+                    &ink_e2e::alice(),
+                    &mut receiver_constructor,
+                )
+                .submit()
+                .await
+                .expect("mock_receiver instantiate failed");
+
+            for token_call in [&mut erc20_call, &mut other_erc20_call] {
+                client
+                    .call(
+                        &ink_e2e::alice(),
+                        &token_call.transfer(lender.account_id, 250_000),
+                    )
+                    .submit()
+                    .await
+                    .expect("lender funding failed");
+                client
+                    .call(
+                        &ink_e2e::alice(),
+                        &token_call.transfer(receiver.account_id, 1_000),
+                    )
+                    .submit()
+                    .await
+                    .expect("receiver funding failed");
+            }
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.set_reentry_allowed(receiver.account_id, true),
+                )
+                .submit()
+                .await
+                .expect("set_reentry_allowed failed");
+
+            let same_token = Instructions {
+                repayment: Repayment::Transfer,
+                nested_loan: Some(NestedLoan {
+                    token: erc20.account_id,
+                    amount: 1_000,
+                    repayment: Repayment::Transfer,
+                    expected: Err(Error::ReentrantLoan),
+                }),
+            };
+            let flash_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash_loan(
+                        receiver.account_id,
+                        erc20.account_id,
+                        100_000,
+                        same_token.encode(),
+                    ),
+                )
+                .submit()
+                .await
+                .expect("flash_loan failed");
+            assert_eq!(flash_loan.return_value(), Ok(true));
+
+            let other_token = Instructions {
+                repayment: Repayment::Transfer,
+                nested_loan: Some(NestedLoan {
+                    token: other_erc20.account_id,
+                    amount: 10_000,
+                    repayment: Repayment::Transfer,
+                    expected: Ok(true),
+                }),
+            };
+            let flash_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash_loan(
+                        receiver.account_id,
+                        erc20.account_id,
+                        100_000,
+                        other_token.encode(),
+                    ),
+                )
+                .submit()
+                .await
+                .expect("flash_loan failed");
+            assert_eq!(flash_loan.return_value(), Ok(true));
+
+            let lender_balance = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(lender.account_id))
+                .dry_run()
+                .await?;
+            assert_eq!(lender_balance.return_value(), 250_200);
+            let other_lender_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &other_erc20_call.balance_of(lender.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(other_lender_balance.return_value(), 250_010);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn unsupported_token_is_rejected<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+
+            let mut lender_constructor = FlashLenderRef::new(vec![], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let lender_call = lender.call_builder::<FlashLender>();
+
+            let max_flash_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.max_flash_loan(erc20.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(
+                max_flash_loan.return_value(),
+                Err(Error::UnsupportedCurrency)
+            );
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn only_owner_manages_supported_tokens<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+
+            let mut lender_constructor = FlashLenderRef::new(vec![], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut lender_call = lender.call_builder::<FlashLender>();
+
+            let result = client
+                .call(
+                    &ink_e2e::bob(),
+                    &lender_call.add_supported_token(erc20.account_id, FeeSchedule::flat(10)),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(result.return_value(), Err(Error::Unauthorized));
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.add_supported_token(erc20.account_id, FeeSchedule::flat(10)),
+                )
+                .submit()
+                .await
+                .expect("add_supported_token failed");
+            let flash_fee = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash_fee(erc20.account_id, 100_000),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(flash_fee.return_value(), Ok(100));
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.remove_supported_token(erc20.account_id),
+                )
+                .submit()
+                .await
+                .expect("remove_supported_token failed");
+            let flash_fee = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash_fee(erc20.account_id, 100_000),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(flash_fee.return_value(), Err(Error::UnsupportedCurrency));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn flash_reverts_without_a_repaying_callback<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut lender_call = lender.call_builder::<FlashLender>();
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(lender.account_id, 250_000),
+                )
+                .submit()
+                .await
+                .expect("transfer failed");
+
+            let unsupported = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash(bob, lender.account_id, 1_000, Vec::new(), bob, [0; 4]),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(unsupported.return_value(), Err(Error::UnsupportedCurrency));
+
+            // The token contract has no message with this selector to call back.
+            let no_callback = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash(
+                        bob,
+                        erc20.account_id,
+                        1_000,
+                        Vec::new(),
+                        erc20.account_id,
+                        [0xde, 0xad, 0xbe, 0xef],
+                    ),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(
+                no_callback.return_value(),
+                Err(Error::ERC3156BorrowerLoanError)
+            );
+
+            let max_flash_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.max_flash_loan(erc20.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(max_flash_loan.return_value(), Ok(250_000));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn flash_pays_the_loan_receiver_and_returns_the_callback_data<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut lender_call = lender.call_builder::<FlashLender>();
+
+            let mut receiver_constructor = MockReceiverRef::new(lender.account_id);
+            let receiver = client
+                .instantiate(
+                    "mock_receiver",
+                    &ink_e2e::alice(),
+                    &mut receiver_constructor,
+                )
+                .submit()
+                .await
+                .expect("mock_receiver instantiate failed");
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(lender.account_id, 250_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+            // The callback pays the loan back from its own balance, while the
+            // borrowed tokens go to bob.
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(receiver.account_id, 100_100),
+                )
+                .submit()
+                .await
+                .expect("receiver funding failed");
+            let bob_before = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(bob))
+                .dry_run()
+                .await?
+                .return_value();
+
+            let data = Instructions::from(Repayment::Transfer).encode();
+            let flash = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash(
+                        bob,
+                        erc20.account_id,
+                        100_000,
+                        data.clone(),
+                        receiver.account_id,
+                        ON_FLASH_SELECTOR,
+                    ),
+                )
+                .submit()
+                .await
+                .expect("flash failed");
+            assert_eq!(flash.return_value(), Ok(data));
+
+            let bob_balance = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(bob))
+                .dry_run()
+                .await?;
+            assert_eq!(bob_balance.return_value(), bob_before + 100_000);
+            let lender_balance = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(lender.account_id))
+                .dry_run()
+                .await?;
+            assert_eq!(lender_balance.return_value(), 250_100);
+            let receiver_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.balance_of(receiver.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(receiver_balance.return_value(), 0);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn native_balance_is_deposited_and_lent<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let mut lender_constructor = FlashLenderRef::new(vec![NATIVE_TOKEN], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut lender_call = lender.call_builder::<FlashLender>();
+
+            let unpaid = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.deposit(NATIVE_TOKEN, 1_000_000),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(unpaid.return_value(), Err(Error::InvalidTransferredValue));
+            let deposit = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.deposit(NATIVE_TOKEN, 1_000_000),
+                )
+                .value(1_000_000)
+                .submit()
+                .await
+                .expect("deposit failed");
+            assert_eq!(deposit.return_value(), Ok(1_000_000));
+
+            let max_flash_loan = client
+                .call(&ink_e2e::alice(), &lender_call.max_flash_loan(NATIVE_TOKEN))
+                .dry_run()
+                .await?;
+            assert_eq!(max_flash_loan.return_value(), Ok(1_000_000));
+            let flash_fee = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash_fee(NATIVE_TOKEN, 100_000),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(flash_fee.return_value(), Ok(100));
+
+            // The native balance is only lent through `native_flash_loan`.
+            let erc20_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash_loan(bob, NATIVE_TOKEN, 100_000, Vec::new()),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(erc20_loan.return_value(), Err(Error::UnsupportedCurrency));
+            // The token contract has no native flash loan callback, so the loan reverts.
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let native_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.native_flash_loan(erc20.account_id, 100_000, Vec::new()),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(
+                native_loan.return_value(),
+                Err(Error::ERC3156BorrowerLoanError)
+            );
+
+            let withdrawn = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.withdraw(NATIVE_TOKEN, 1_000_000),
+                )
+                .submit()
+                .await
+                .expect("withdraw failed");
+            assert_eq!(withdrawn.return_value(), Ok(1_000_000));
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn native_loan_is_sent_with_the_callback_and_repaid<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut lender_constructor = FlashLenderRef::new(vec![NATIVE_TOKEN], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut lender_call = lender.call_builder::<FlashLender>();
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.deposit(NATIVE_TOKEN, 1_000_000),
+                )
+                .value(1_000_000)
+                .submit()
+                .await
+                .expect("deposit failed");
+
+            // The receiver is endowed with enough to pay the fee.
+            let mut receiver_constructor = MockReceiverRef::new(lender.account_id);
+            let receiver = client
+                .instantiate(
+                    "mock_receiver",
+                    &ink_e2e::alice(),
+                    &mut receiver_constructor,
+                )
+                .value(1_000)
+                .submit()
+                .await
+                .expect("mock_receiver instantiate failed");
+            let receiver_before = client.free_balance(receiver.account_id).await?;
+
+            let short = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.native_flash_loan(
+                        receiver.account_id,
+                        100_000,
+                        Instructions::from(Repayment::Short(1)).encode(),
+                    ),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(
+                short.return_value(),
+                Err(Error::RepaymentShortfall {
+                    balance: 1_000_099,
+                    needed: 1_000_100,
+                })
+            );
+
+            let native_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.native_flash_loan(
+                        receiver.account_id,
+                        100_000,
+                        Instructions::from(Repayment::Transfer).encode(),
+                    ),
+                )
+                .submit()
+                .await
+                .expect("native_flash_loan failed");
+            assert_eq!(native_loan.return_value(), Ok(true));
+
+            let max_flash_loan = client
+                .call(&ink_e2e::alice(), &lender_call.max_flash_loan(NATIVE_TOKEN))
+                .dry_run()
+                .await?;
+            assert_eq!(max_flash_loan.return_value(), Ok(1_000_100));
+            let receiver_after = client.free_balance(receiver.account_id).await?;
+            assert_eq!(receiver_before - receiver_after, 100);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn flash_fee_honours_fee_schedule<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let token = ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie);
+            let mut lender_constructor = FlashLenderRef::new(vec![], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut lender_call = lender.call_builder::<FlashLender>();
+
+            let fee_schedule = FeeSchedule {
+                rate: 10,
+                min_fee: 5,
+                max_fee: Some(1_000),
+            };
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.add_supported_token(token, fee_schedule),
+                )
+                .submit()
+                .await
+                .expect("add_supported_token failed");
+
+            for (amount, expected) in [(1_000, 5), (100_000, 100), (10_000_000, 1_000)] {
+                let flash_fee = client
+                    .call(&ink_e2e::alice(), &lender_call.flash_fee(token, amount))
+                    .dry_run()
+                    .await?;
+                assert_eq!(flash_fee.return_value(), Ok(expected));
+            }
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn guardian_pauses_lending<Client: E2EBackend>(mut client: Client) -> E2EResult<()> {
+            let token = ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie);
+            let guardian = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let mut lender_constructor = FlashLenderRef::new(vec![token], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut lender_call = lender.call_builder::<FlashLender>();
+
+            client
+                .call(&ink_e2e::alice(), &lender_call.set_guardian(Some(guardian)))
+                .submit()
+                .await
+                .expect("set_guardian failed");
+            client
+                .call(&ink_e2e::bob(), &lender_call.pause_token(token))
+                .submit()
+                .await
+                .expect("pause_token failed");
+
+            let flash_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &lender_call.flash_loan(guardian, token, 1_000, vec![]),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(flash_loan.return_value(), Err(Error::Paused));
+            let max_flash_loan = client
+                .call(&ink_e2e::alice(), &lender_call.max_flash_loan(token))
+                .dry_run()
+                .await?;
+            assert_eq!(max_flash_loan.return_value(), Ok(0));
+            let flash_fee = client
+                .call(&ink_e2e::alice(), &lender_call.flash_fee(token, 100_000))
+                .dry_run()
+                .await?;
+            assert_eq!(flash_fee.return_value(), Ok(100));
+
+            let unpause = client
+                .call(&ink_e2e::bob(), &lender_call.unpause_token(token))
+                .dry_run()
+                .await?;
+            assert_eq!(unpause.return_value(), Err(Error::Unauthorized));
+
+            Ok(())
         }
     }
 }
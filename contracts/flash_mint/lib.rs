@@ -0,0 +1,507 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::flash_mint::{FlashMint, FlashMintRef};
+
+#[ink::contract]
+mod flash_mint {
+    use ierc20::{Erc20Core, Result, IERC20};
+    use ierc3156::{
+        ierc3156_flash_borrower::IERC3156FlashBorrower,
+        ierc3156_flash_lender::{
+            Error as LenderError, IERC3156FlashLender, Result as LenderResult,
+        },
+    };
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::{hash::Keccak256, CallFlags};
+    use ink::prelude::vec::Vec;
+
+    #[ink(storage)]
+    pub struct FlashMint {
+        /// Balances, allowances and supply of the token.
+        token: Erc20Core,
+        /// Fee charged on every flash mint (1 = 0.01%).
+        fee: u128,
+        /// Account receiving the flash mint fees.
+        fee_receiver: AccountId,
+    }
+
+    impl IERC20 for FlashMint {
+        /// Returns the total token supply.
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self.token.total_supply()
+        }
+
+        /// Returns the balance of the given `account`.
+        #[ink(message)]
+        fn balance_of(&self, account: AccountId) -> Balance {
+            self.token.balance_of(account)
+        }
+
+        /// Transfers `value` tokens from the caller's account to `to`.
+        ///
+        /// Returns `true` if the operation succeeded.
+        ///
+        /// Emits a `Transfer` event.
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, value: Balance) -> Result<bool> {
+            self.token.transfer(self.env().caller(), to, value)?;
+            Ok(true)
+        }
+
+        /// Returns the remaining number of tokens that `spender` can spend
+        /// on behalf of `owner` through `transfer_from`.
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.token.allowance(owner, spender)
+        }
+
+        /// Sets `value` as the allowance of `spender` over the caller’s tokens.
+        ///
+        /// Returns `true` if the operation succeeded.
+        ///
+        /// Emits an `Approval` event.
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<bool> {
+            self.token.approve(self.env().caller(), spender, value)?;
+            Ok(true)
+        }
+
+        /// Transfers `value` tokens from `from` to `to` using the allowance mechanism.
+        /// `value` is then deducted from the caller’s allowance.
+        ///
+        /// Returns `true` if the operation succeeded.
+        ///
+        /// Emits a `Transfer` event.
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<bool> {
+            self.token
+                .transfer_from(self.env().caller(), from, to, value)?;
+            Ok(true)
+        }
+    }
+
+    impl IERC3156FlashLender for FlashMint {
+        /// Mint `amount` tokens to `receiver`, and burn them back plus collect a
+        /// `flashFee` after the callback.
+        ///
+        /// The repayment is taken through the allowance granted by `receiver`
+        /// to this contract.
+        ///
+        /// ## Params:
+        /// - `receiver`: The contract receiving the tokens.  
+        ///   Must implement the `onFlashLoan(address user, uint256 amount, uint256 fee, bytes calldata)` interface.
+        /// - `token`: The loan currency. Must be this contract.
+        /// - `amount`: The amount of tokens lent.
+        /// - `data`: A data parameter to be passed on to the `receiver` for any custom use.
+        ///
+        /// ## Returns:
+        /// - `bool`: True if the flash loan succeeds.
+        #[ink(message)]
+        fn flash_loan(
+            &mut self,
+            receiver: AccountId,
+            token: AccountId,
+            amount: u128,
+            data: Vec<u8>,
+        ) -> LenderResult<bool> {
+            if amount > self.max_flash_loan(token)? {
+                return Err(LenderError::AmountExceedsMaxLoan);
+            }
+            let fee = self._flash_fee(amount);
+            self.token
+                .mint(receiver, amount)
+                .map_err(LenderError::ERC20Error)?;
+            let callback_result = self._call_ierc3156_flash_borrower_callback(
+                receiver,
+                self.env().caller(),
+                amount,
+                fee,
+                data,
+            )?;
+            if callback_result
+                != self
+                    .env()
+                    .hash_bytes::<Keccak256>(b"ERC3156FlashBorrower.onFlashLoan")
+            {
+                return Err(LenderError::CallbackFailed);
+            }
+            self._collect_repayment(receiver, amount, fee)
+                .map_err(LenderError::ERC20Error)?;
+            Ok(true)
+        }
+
+        /// The fee to be charged for a given loan.
+        ///
+        /// ## Params:
+        /// - `token`: The loan currency. Must be this contract.
+        /// - `amount`: The amount of tokens lent.
+        ///
+        /// ## Returns:
+        /// - `u128`: The fee to be charged on top of the returned principal.
+        #[ink(message)]
+        fn flash_fee(&self, token: AccountId, amount: u128) -> LenderResult<u128> {
+            self.ensure_self(token)?;
+            Ok(self._flash_fee(amount))
+        }
+
+        /// The amount of currency available to be lent, i.e. the tokens that can
+        /// still be minted without overflowing the total supply.
+        ///
+        /// ## Params:
+        /// - `token`: The loan currency. Must be this contract.
+        ///
+        /// ## Returns:
+        /// - `u128`: The amount of `token` that can be borrowed.
+        #[ink(message)]
+        fn max_flash_loan(&self, token: AccountId) -> LenderResult<u128> {
+            self.ensure_self(token)?;
+            Ok(Balance::MAX - self.token.total_supply())
+        }
+    }
+
+    impl FlashMint {
+        /// Creates a new [`FlashMint`] minting `total_supply` tokens to the caller.
+        ///
+        /// The caller also receives the flash mint fees.
+        ///
+        /// ## Params:
+        /// - `total_supply`: Amount of tokens minted to the deployer.
+        /// - `fee`: The percentage of the loan `amount` that needs to be repaid,
+        ///   in addition to `amount`. (1 == 0.01%).
+        #[ink(constructor)]
+        pub fn new(total_supply: Balance, fee: u128) -> Self {
+            let mut token = Erc20Core::default();
+            token
+                .mint(Self::env().caller(), total_supply)
+                .expect("the deployer is never the zero account");
+            Self {
+                token,
+                fee,
+                fee_receiver: Self::env().caller(),
+            }
+        }
+
+        /// Internal function taking back a loan of `amount` plus `fee` from
+        /// `receiver` through its allowance: the principal is burned and the fee
+        /// sent to the fee receiver.
+        ///
+        /// ## Errors:
+        /// - `InsufficientAllowance` if `receiver` approved less than `amount + fee`.
+        /// - `InsufficientBalance` if `receiver` holds less than `amount + fee`.
+        fn _collect_repayment(
+            &mut self,
+            receiver: AccountId,
+            amount: u128,
+            fee: u128,
+        ) -> Result<()> {
+            self.token
+                .spend_allowance(receiver, self.env().account_id(), amount + fee)?;
+            self.token.burn(receiver, amount)?;
+            if fee > 0 {
+                self.token.transfer(receiver, self.fee_receiver, fee)?;
+            }
+            Ok(())
+        }
+
+        /// Internal function returning the fee to be charged for a given loan.
+        ///
+        /// ## Params:
+        /// - `amount`: The amount of tokens lent.
+        fn _flash_fee(&self, amount: u128) -> u128 {
+            amount.saturating_mul(self.fee) / 10000
+        }
+
+        /// Returns `UnsupportedCurrency` unless `token` is this contract.
+        fn ensure_self(&self, token: AccountId) -> LenderResult<()> {
+            if token != self.env().account_id() {
+                return Err(LenderError::UnsupportedCurrency);
+            }
+            Ok(())
+        }
+
+        /// Calls the `on_flash_loan` callback on an `IERC3156FlashBorrower` contract.
+        ///
+        /// The borrower is allowed to call back into this token, e.g. to approve
+        /// the repayment.
+        ///
+        /// ## Params:
+        /// - `receiver`: The `IERC3156FlashBorrower` contract receiving the loan.
+        /// - `sender`: who initiated tx.
+        /// - `amount`: Principal amount borrowed.
+        /// - `fee`: Additional fee required for repayment.
+        /// - `data`: Arbitrary bytes data passed through to the borrower.
+        ///
+        /// ## Returns:
+        /// - The hash returned by the borrower.
+        fn _call_ierc3156_flash_borrower_callback(
+            &self,
+            receiver: AccountId,
+            sender: AccountId,
+            amount: u128,
+            fee: u128,
+            data: Vec<u8>,
+        ) -> LenderResult<[u8; 32]> {
+            let borrower: ink::contract_ref!(IERC3156FlashBorrower) = receiver.into();
+            borrower
+                .call()
+                .on_flash_loan(sender, self.env().account_id(), amount, fee, data)
+                .call_flags(CallFlags::ALLOW_REENTRY)
+                .invoke()
+                .map_err(|_| LenderError::ERC3156BorrowerLoanError)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ierc20::Error;
+
+        fn accounts() -> ink::env::test::DefaultAccounts<Environment> {
+            ink::env::test::default_accounts::<Environment>()
+        }
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<Environment>(caller);
+        }
+
+        fn contract_id() -> AccountId {
+            ink::env::account_id::<Environment>()
+        }
+
+        #[ink::test]
+        fn max_flash_loan_is_the_unminted_supply() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let token = FlashMint::new(1_000_000, 10);
+
+            assert_eq!(
+                token.max_flash_loan(contract_id()),
+                Ok(Balance::MAX - 1_000_000)
+            );
+            assert_eq!(token.flash_fee(contract_id(), 100_000), Ok(100));
+            assert_eq!(
+                token.max_flash_loan(accounts.django),
+                Err(LenderError::UnsupportedCurrency)
+            );
+        }
+
+        #[ink::test]
+        fn loans_above_max_flash_loan_are_rejected() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut token = FlashMint::new(1_000_000, 10);
+
+            assert_eq!(
+                token.flash_loan(
+                    accounts.bob,
+                    contract_id(),
+                    Balance::MAX - 1_000_000 + 1,
+                    Vec::new()
+                ),
+                Err(LenderError::AmountExceedsMaxLoan)
+            );
+            assert_eq!(token.total_supply(), 1_000_000);
+        }
+
+        #[ink::test]
+        fn repayment_burns_principal_and_pays_fee() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut token = FlashMint::new(1_000_000, 10);
+            assert_eq!(token.transfer(accounts.bob, 100), Ok(true));
+            // The loan minted to the borrower, approved back to the token.
+            token.token.mint(accounts.bob, 100_000).unwrap();
+            set_caller(accounts.bob);
+            assert_eq!(token.approve(contract_id(), 100_100), Ok(true));
+
+            assert_eq!(token._collect_repayment(accounts.bob, 100_000, 100), Ok(()));
+            assert_eq!(token.total_supply(), 1_000_000);
+            assert_eq!(token.balance_of(accounts.bob), 0);
+            assert_eq!(token.balance_of(accounts.alice), 1_000_000);
+            assert_eq!(token.allowance(accounts.bob, contract_id()), 0);
+        }
+
+        #[ink::test]
+        fn repayment_requires_allowance() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut token = FlashMint::new(1_000_000, 10);
+            assert_eq!(token.transfer(accounts.bob, 100), Ok(true));
+            token.token.mint(accounts.bob, 100_000).unwrap();
+
+            assert_eq!(
+                token._collect_repayment(accounts.bob, 100_000, 100),
+                Err(Error::InsufficientAllowance {
+                    spender: contract_id(),
+                    allowance: 0,
+                    needed: 100_100,
+                })
+            );
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use flash_borrower::{FlashBorrower, FlashBorrowerRef};
+        use ink::scale::Encode;
+        use ink_e2e::ContractsBackend;
+        use mock_receiver::{Instructions, MockReceiverRef, NestedLoan, Repayment};
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        #[ink_e2e::test]
+        async fn flash_mint_burns_principal_and_collects_fee<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let alice = ink_e2e::account_id(ink_e2e::AccountKeyring::Alice);
+            let mut token_constructor = FlashMintRef::new(1_000_000, 10);
+            let token = client
+                .instantiate("flash_mint", &ink_e2e::alice(), &mut token_constructor)
+                .submit()
+                .await
+                .expect("flash_mint instantiate failed");
+            let mut token_call = token.call_builder::<FlashMint>();
+
+            let mut borrower_constructor = FlashBorrowerRef::new(token.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_call.transfer(borrower.account_id, 1_000),
+                )
+                .submit()
+                .await
+                .expect("borrower funding failed");
+
+            let max_flash_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_call.max_flash_loan(token.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(max_flash_loan.return_value(), Ok(Balance::MAX - 1_000_000));
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow(token.account_id, 100_000, Vec::new(), None),
+                )
+                .submit()
+                .await
+                .expect("flash_borrow failed");
+
+            let total_supply = client
+                .call(&ink_e2e::alice(), &token_call.total_supply())
+                .dry_run()
+                .await?;
+            assert_eq!(total_supply.return_value(), 1_000_000);
+            let borrower_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_call.balance_of(borrower.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(borrower_balance.return_value(), 900);
+            let fee_receiver_balance = client
+                .call(&ink_e2e::alice(), &token_call.balance_of(alice))
+                .dry_run()
+                .await?;
+            assert_eq!(fee_receiver_balance.return_value(), 999_100);
+
+            Ok(())
+        }
+
+        #[ink_e2e::test]
+        async fn outstanding_loan_counts_towards_the_supply<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut token_constructor = FlashMintRef::new(1_000_000, 10);
+            let token = client
+                .instantiate("flash_mint", &ink_e2e::alice(), &mut token_constructor)
+                .submit()
+                .await
+                .expect("flash_mint instantiate failed");
+            let mut token_call = token.call_builder::<FlashMint>();
+
+            let mut receiver_constructor = MockReceiverRef::new(token.account_id);
+            let receiver = client
+                .instantiate(
+                    "mock_receiver",
+                    &ink_e2e::alice(),
+                    &mut receiver_constructor,
+                )
+                .submit()
+                .await
+                .expect("mock_receiver instantiate failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_call.transfer(receiver.account_id, 100),
+                )
+                .submit()
+                .await
+                .expect("receiver funding failed");
+
+            // While 100_000 tokens are lent, `max_flash_loan` has shrunk by as much,
+            // so a nested loan of the supply left before the loan is rejected.
+            let instructions = Instructions {
+                repayment: Repayment::Approve,
+                nested_loan: Some(NestedLoan {
+                    token: token.account_id,
+                    amount: Balance::MAX - 1_000_000 - 100_000 + 1,
+                    repayment: Repayment::Approve,
+                    expected: Err(LenderError::AmountExceedsMaxLoan),
+                }),
+            };
+            let flash_loan = client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_call.flash_loan(
+                        receiver.account_id,
+                        token.account_id,
+                        100_000,
+                        instructions.encode(),
+                    ),
+                )
+                .submit()
+                .await
+                .expect("flash_loan failed");
+            assert_eq!(flash_loan.return_value(), Ok(true));
+
+            let total_supply = client
+                .call(&ink_e2e::alice(), &token_call.total_supply())
+                .dry_run()
+                .await?;
+            assert_eq!(total_supply.return_value(), 1_000_000);
+            let receiver_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &token_call.balance_of(receiver.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(receiver_balance.return_value(), 0);
+
+            Ok(())
+        }
+    }
+}
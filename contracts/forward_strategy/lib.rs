@@ -0,0 +1,60 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::forward_strategy::{ForwardStrategy, ForwardStrategyRef};
+
+/// A minimal `IFlashLoanStrategy`, forwarding the loan plus its fees to a
+/// recipient.
+#[ink::contract]
+mod forward_strategy {
+    use ierc20::IERC20;
+    use ierc3156::ierc3156_flash_borrower::{Error, Result};
+    use ierc3156::ierc3156_flash_strategy::IFlashLoanStrategy;
+    use ink::prelude::vec::Vec;
+    use ink::scale::Decode;
+
+    /// Declares no storage, so it can also run through a delegate call.
+    #[ink(storage)]
+    #[derive(Default)]
+    pub struct ForwardStrategy {}
+
+    impl IFlashLoanStrategy for ForwardStrategy {
+        /// Transfers `amount + fee` of every lent token from the executing account
+        /// to the recipient encoded in `params`.
+        ///
+        /// Called as a contract with the borrower as recipient, it hands back the
+        /// loan plus the fees it was funded with. Run through a delegate call, it
+        /// pays the recipient from the borrower balance.
+        ///
+        /// ## Parameters:
+        /// - `tokens`: The addresses of the tokens that were lent.
+        /// - `amounts`: The amount borrowed of each token.
+        /// - `fees`: The fee charged by the lender for each token.
+        /// - `params`: The encoded `AccountId` of the recipient.
+        #[ink(message)]
+        fn execute(
+            &self,
+            tokens: Vec<AccountId>,
+            amounts: Vec<u128>,
+            fees: Vec<u128>,
+            params: Vec<u8>,
+        ) -> Result<()> {
+            let recipient =
+                AccountId::decode(&mut &params[..]).map_err(|_| Error::ScaleDecodingErr)?;
+            for ((token, amount), fee) in tokens.into_iter().zip(amounts).zip(fees) {
+                let mut erc20: ink::contract_ref!(IERC20) = token.into();
+                erc20
+                    .transfer(recipient, amount + fee)
+                    .map_err(Error::ERC20Error)?;
+            }
+            Ok(())
+        }
+    }
+
+    impl ForwardStrategy {
+        /// Creates a new [`ForwardStrategy`].
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {}
+        }
+    }
+}
@@ -0,0 +1,539 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::lending_market::{LendingMarket, LendingMarketRef};
+
+#[ink::contract]
+mod lending_market {
+    use ierc20::IERC20;
+    use ilending::{Error, ILendingMarket, Position, Result, PRICE_PRECISION};
+    use ink::storage::Mapping;
+
+    const BPS: u128 = 10_000;
+
+    /// A mock lending market with owner-set prices.
+    ///
+    /// Every account holds a single position. Lending liquidity is whatever
+    /// balance of the debt token the market holds.
+    #[ink(storage)]
+    pub struct LendingMarket {
+        /// Account allowed to set prices.
+        owner: AccountId,
+        /// Price of each listed token, scaled by `PRICE_PRECISION`.
+        prices: Mapping<AccountId, u128>,
+        /// Position of each account.
+        positions: Mapping<AccountId, Position>,
+        /// Share of the collateral value that can be borrowed, in basis points.
+        collateral_factor: u128,
+        /// Extra collateral paid to liquidators, in basis points of the repaid debt.
+        liquidation_bonus: u128,
+    }
+
+    /// Emitted when the owner sets the price of a token.
+    #[ink(event)]
+    pub struct PriceUpdated {
+        #[ink(topic)]
+        token: AccountId,
+        price: u128,
+    }
+
+    /// Emitted when an account deposits collateral.
+    #[ink(event)]
+    pub struct CollateralDeposited {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        token: AccountId,
+        amount: Balance,
+    }
+
+    /// Emitted when an account withdraws collateral.
+    #[ink(event)]
+    pub struct CollateralWithdrawn {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        token: AccountId,
+        amount: Balance,
+    }
+
+    /// Emitted when an account borrows.
+    #[ink(event)]
+    pub struct Borrowed {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        token: AccountId,
+        amount: Balance,
+    }
+
+    /// Emitted when an account repays its debt.
+    #[ink(event)]
+    pub struct Repaid {
+        #[ink(topic)]
+        account: AccountId,
+        #[ink(topic)]
+        token: AccountId,
+        amount: Balance,
+    }
+
+    /// Emitted when an unhealthy position is liquidated.
+    #[ink(event)]
+    pub struct Liquidated {
+        #[ink(topic)]
+        borrower: AccountId,
+        #[ink(topic)]
+        liquidator: AccountId,
+        debt_token: AccountId,
+        repaid: Balance,
+        collateral_token: AccountId,
+        seized: Balance,
+    }
+
+    impl ILendingMarket for LendingMarket {
+        /// Returns the price of `token`, scaled by `PRICE_PRECISION`. Zero if unlisted.
+        #[ink(message)]
+        fn price(&self, token: AccountId) -> u128 {
+            self.prices.get(token).unwrap_or_default()
+        }
+
+        /// Returns the position of `account`.
+        #[ink(message)]
+        fn position(&self, account: AccountId) -> Position {
+            self.positions.get(account).unwrap_or_default()
+        }
+
+        /// Returns whether the debt of `account` is covered by its collateral.
+        ///
+        /// A position is healthy while its debt value is at most its collateral
+        /// value times the collateral factor.
+        #[ink(message)]
+        fn is_healthy(&self, account: AccountId) -> bool {
+            self._is_healthy(&self.position(account)).unwrap_or(false)
+        }
+
+        /// Deposits `amount` of `token`, pulled from the caller, as collateral.
+        ///
+        /// ## Errors:
+        /// - `ZeroAmount` if `amount` is zero.
+        /// - `UnlistedToken` if `token` has no price.
+        /// - `CollateralTokenMismatch` if the position holds another collateral token.
+        /// - `ERC20Error` if pulling the tokens fails.
+        #[ink(message)]
+        fn deposit_collateral(&mut self, token: AccountId, amount: Balance) -> Result<()> {
+            self._ensure_listed(token, amount)?;
+            let account = self.env().caller();
+            let mut position = self.position(account);
+            if position.collateral_token.is_some_and(|t| t != token) {
+                return Err(Error::CollateralTokenMismatch);
+            }
+            self._call_erc20_transfer_from(token, account, amount)?;
+            position.collateral_token = Some(token);
+            position.collateral = position
+                .collateral
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.positions.insert(account, &position);
+            self.env().emit_event(CollateralDeposited {
+                account,
+                token,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Withdraws `amount` of the caller's collateral `token`.
+        ///
+        /// ## Errors:
+        /// - `ZeroAmount` if `amount` is zero.
+        /// - `CollateralTokenMismatch` if `token` is not the collateral token of the position.
+        /// - `InsufficientCollateral` if `amount` exceeds the collateral.
+        /// - `Undercollateralized` if the position would become unhealthy.
+        /// - `ERC20Error` if sending the tokens fails.
+        #[ink(message)]
+        fn withdraw_collateral(&mut self, token: AccountId, amount: Balance) -> Result<()> {
+            if amount == 0 {
+                return Err(Error::ZeroAmount);
+            }
+            let account = self.env().caller();
+            let mut position = self.position(account);
+            if position.collateral_token != Some(token) {
+                return Err(Error::CollateralTokenMismatch);
+            }
+            position.collateral = position
+                .collateral
+                .checked_sub(amount)
+                .ok_or(Error::InsufficientCollateral)?;
+            if position.collateral == 0 {
+                position.collateral_token = None;
+            }
+            if !self._is_healthy(&position)? {
+                return Err(Error::Undercollateralized);
+            }
+            self.positions.insert(account, &position);
+            self._call_erc20_transfer(token, account, amount)?;
+            self.env().emit_event(CollateralWithdrawn {
+                account,
+                token,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Borrows `amount` of `token` against the caller's collateral.
+        ///
+        /// ## Errors:
+        /// - `ZeroAmount` if `amount` is zero.
+        /// - `UnlistedToken` if `token` has no price.
+        /// - `DebtTokenMismatch` if the position owes another token.
+        /// - `InsufficientLiquidity` if the market holds less than `amount`.
+        /// - `Undercollateralized` if the position would become unhealthy.
+        /// - `ERC20Error` if sending the tokens fails.
+        #[ink(message)]
+        fn borrow(&mut self, token: AccountId, amount: Balance) -> Result<()> {
+            self._ensure_listed(token, amount)?;
+            let account = self.env().caller();
+            let mut position = self.position(account);
+            if position.debt_token.is_some_and(|t| t != token) {
+                return Err(Error::DebtTokenMismatch);
+            }
+            if self._call_erc20_balance_of(token) < amount {
+                return Err(Error::InsufficientLiquidity);
+            }
+            position.debt_token = Some(token);
+            position.debt = position.debt.checked_add(amount).ok_or(Error::Overflow)?;
+            if !self._is_healthy(&position)? {
+                return Err(Error::Undercollateralized);
+            }
+            self.positions.insert(account, &position);
+            self._call_erc20_transfer(token, account, amount)?;
+            self.env().emit_event(Borrowed {
+                account,
+                token,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Repays `amount` of the caller's debt in `token`, pulled from the caller.
+        ///
+        /// ## Errors:
+        /// - `ZeroAmount` if `amount` is zero.
+        /// - `DebtTokenMismatch` if the position does not owe `token`.
+        /// - `RepayExceedsDebt` if `amount` exceeds the debt.
+        /// - `ERC20Error` if pulling the tokens fails.
+        #[ink(message)]
+        fn repay(&mut self, token: AccountId, amount: Balance) -> Result<()> {
+            if amount == 0 {
+                return Err(Error::ZeroAmount);
+            }
+            let account = self.env().caller();
+            let mut position = self.position(account);
+            Self::reduce_debt(&mut position, token, amount)?;
+            self._call_erc20_transfer_from(token, account, amount)?;
+            self.positions.insert(account, &position);
+            self.env().emit_event(Repaid {
+                account,
+                token,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Repays `amount` of the debt of an unhealthy `borrower` in `debt_token`,
+        /// pulled from the caller, and sends the caller the collateral worth the
+        /// repaid debt plus the liquidation bonus, capped at the whole collateral.
+        ///
+        /// ## Returns:
+        /// - The amount of collateral sent to the caller.
+        ///
+        /// ## Errors:
+        /// - `ZeroAmount` if `amount` is zero.
+        /// - `PositionHealthy` if the position of `borrower` is healthy.
+        /// - `DebtTokenMismatch` if the position does not owe `debt_token`.
+        /// - `RepayExceedsDebt` if `amount` exceeds the debt.
+        /// - `UnlistedToken` if the collateral token has no price.
+        /// - `ERC20Error` if moving the tokens fails.
+        #[ink(message)]
+        fn liquidate(
+            &mut self,
+            borrower: AccountId,
+            debt_token: AccountId,
+            amount: Balance,
+        ) -> Result<Balance> {
+            if amount == 0 {
+                return Err(Error::ZeroAmount);
+            }
+            let mut position = self.position(borrower);
+            if self._is_healthy(&position)? {
+                return Err(Error::PositionHealthy);
+            }
+            Self::reduce_debt(&mut position, debt_token, amount)?;
+            let collateral_token = position
+                .collateral_token
+                .ok_or(Error::InsufficientCollateral)?;
+
+            let repaid_value = Self::mul_div(amount, self.price(debt_token), PRICE_PRECISION)?;
+            let seized_value = Self::mul_div(repaid_value, BPS + self.liquidation_bonus, BPS)?;
+            let collateral_price = self.price(collateral_token);
+            if collateral_price == 0 {
+                return Err(Error::UnlistedToken);
+            }
+            let seized = Self::mul_div(seized_value, PRICE_PRECISION, collateral_price)?
+                .min(position.collateral);
+            position.collateral -= seized;
+            if position.collateral == 0 {
+                position.collateral_token = None;
+            }
+
+            let liquidator = self.env().caller();
+            self._call_erc20_transfer_from(debt_token, liquidator, amount)?;
+            self.positions.insert(borrower, &position);
+            self._call_erc20_transfer(collateral_token, liquidator, seized)?;
+            self.env().emit_event(Liquidated {
+                borrower,
+                liquidator,
+                debt_token,
+                repaid: amount,
+                collateral_token,
+                seized,
+            });
+            Ok(seized)
+        }
+    }
+
+    impl LendingMarket {
+        /// Creates a new [`LendingMarket`] owned by the caller.
+        ///
+        /// ## Parameters:
+        /// - `collateral_factor`: Share of the collateral value that can be borrowed, in basis points.
+        /// - `liquidation_bonus`: Extra collateral paid to liquidators, in basis points.
+        #[ink(constructor)]
+        pub fn new(collateral_factor: u128, liquidation_bonus: u128) -> Self {
+            Self {
+                owner: Self::env().caller(),
+                prices: Mapping::default(),
+                positions: Mapping::default(),
+                collateral_factor,
+                liquidation_bonus,
+            }
+        }
+
+        /// Returns the current owner.
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// Returns the collateral factor, in basis points.
+        #[ink(message)]
+        pub fn collateral_factor(&self) -> u128 {
+            self.collateral_factor
+        }
+
+        /// Returns the liquidation bonus, in basis points.
+        #[ink(message)]
+        pub fn liquidation_bonus(&self) -> u128 {
+            self.liquidation_bonus
+        }
+
+        /// Sets the price of `token`. A zero price unlists it.
+        ///
+        /// ## Parameters:
+        /// - `token`: The token to price.
+        /// - `price`: The price, scaled by `PRICE_PRECISION`.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        #[ink(message)]
+        pub fn set_price(&mut self, token: AccountId, price: u128) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            self.prices.insert(token, &price);
+            self.env().emit_event(PriceUpdated { token, price });
+            Ok(())
+        }
+
+        /// Lowers the debt of `position` by `amount` of `token`.
+        fn reduce_debt(position: &mut Position, token: AccountId, amount: Balance) -> Result<()> {
+            if position.debt_token != Some(token) {
+                return Err(Error::DebtTokenMismatch);
+            }
+            position.debt = position
+                .debt
+                .checked_sub(amount)
+                .ok_or(Error::RepayExceedsDebt)?;
+            if position.debt == 0 {
+                position.debt_token = None;
+            }
+            Ok(())
+        }
+
+        /// Returns whether the debt value of `position` is at most its collateral
+        /// value times the collateral factor.
+        fn _is_healthy(&self, position: &Position) -> Result<bool> {
+            let Some(debt_token) = position.debt_token else {
+                return Ok(true);
+            };
+            let debt_value = Self::mul_div(position.debt, self.price(debt_token), PRICE_PRECISION)?;
+            let collateral_value = match position.collateral_token {
+                Some(token) => {
+                    Self::mul_div(position.collateral, self.price(token), PRICE_PRECISION)?
+                }
+                None => 0,
+            };
+            let borrow_limit = Self::mul_div(collateral_value, self.collateral_factor, BPS)?;
+            Ok(debt_value <= borrow_limit)
+        }
+
+        /// Returns `ZeroAmount` for a zero `amount` and `UnlistedToken` for an unpriced `token`.
+        fn _ensure_listed(&self, token: AccountId, amount: Balance) -> Result<()> {
+            if amount == 0 {
+                return Err(Error::ZeroAmount);
+            }
+            if self.price(token) == 0 {
+                return Err(Error::UnlistedToken);
+            }
+            Ok(())
+        }
+
+        /// Computes `a * b / c`, failing with `Overflow` if `a * b` does not fit.
+        fn mul_div(a: u128, b: u128, c: u128) -> Result<u128> {
+            a.checked_mul(b)
+                .map(|product| product / c)
+                .ok_or(Error::Overflow)
+        }
+
+        /// Calls the ERC20 `balance_of` function for the market on a given token contract.
+        ///
+        /// ## Params:
+        /// - `token`: AccountId of the ERC20 contract.
+        ///
+        /// ## Returns:
+        /// - The balance of the market as `u128`.
+        fn _call_erc20_balance_of(&self, token: AccountId) -> Balance {
+            let erc20: ink::contract_ref!(IERC20) = token.into();
+            erc20.balance_of(self.env().account_id())
+        }
+
+        /// Calls the ERC20 `transfer` function on a given token contract.
+        ///
+        /// ## Params:
+        /// - `token`: AccountId of the ERC20 contract.
+        /// - `to`: AccountId that will receive the tokens.
+        /// - `amount`: Amount of tokens to transfer.
+        fn _call_erc20_transfer(
+            &self,
+            token: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<()> {
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            erc20.transfer(to, amount).map_err(Error::ERC20Error)?;
+            Ok(())
+        }
+
+        /// Calls the ERC20 `transfer_from` function on a given token contract,
+        /// moving `amount` tokens from `from` to the market.
+        ///
+        /// ## Params:
+        /// - `token`: AccountId of the ERC20 contract.
+        /// - `from`: AccountId whose tokens are pulled.
+        /// - `amount`: Amount of tokens to transfer.
+        fn _call_erc20_transfer_from(
+            &self,
+            token: AccountId,
+            from: AccountId,
+            amount: Balance,
+        ) -> Result<()> {
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            erc20
+                .transfer_from(from, self.env().account_id(), amount)
+                .map_err(Error::ERC20Error)?;
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn accounts() -> ink::env::test::DefaultAccounts<Environment> {
+            ink::env::test::default_accounts::<Environment>()
+        }
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<Environment>(caller);
+        }
+
+        fn position(collateral: Balance, debt: Balance) -> Position {
+            let accounts = accounts();
+            Position {
+                collateral_token: Some(accounts.django),
+                collateral,
+                debt_token: Some(accounts.eve),
+                debt,
+            }
+        }
+
+        #[ink::test]
+        fn only_owner_sets_prices() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut market = LendingMarket::new(7_500, 500);
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                market.set_price(accounts.django, PRICE_PRECISION),
+                Err(Error::Unauthorized)
+            );
+            set_caller(accounts.alice);
+            assert_eq!(market.set_price(accounts.django, PRICE_PRECISION), Ok(()));
+            assert_eq!(market.price(accounts.django), PRICE_PRECISION);
+        }
+
+        #[ink::test]
+        fn health_follows_collateral_factor_and_prices() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut market = LendingMarket::new(7_500, 500);
+            market
+                .set_price(accounts.django, PRICE_PRECISION)
+                .expect("owner sets prices");
+            market
+                .set_price(accounts.eve, PRICE_PRECISION)
+                .expect("owner sets prices");
+
+            assert_eq!(market._is_healthy(&position(10_000, 7_500)), Ok(true));
+            assert_eq!(market._is_healthy(&position(10_000, 7_501)), Ok(false));
+            assert_eq!(market._is_healthy(&Position::default()), Ok(true));
+
+            market
+                .set_price(accounts.django, PRICE_PRECISION * 8 / 10)
+                .expect("owner sets prices");
+            assert_eq!(market._is_healthy(&position(10_000, 6_000)), Ok(true));
+            assert_eq!(market._is_healthy(&position(10_000, 7_000)), Ok(false));
+        }
+
+        #[ink::test]
+        fn healthy_positions_cannot_be_liquidated() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut market = LendingMarket::new(7_500, 500);
+            market
+                .set_price(accounts.django, PRICE_PRECISION)
+                .expect("owner sets prices");
+            market
+                .set_price(accounts.eve, PRICE_PRECISION)
+                .expect("owner sets prices");
+            market
+                .positions
+                .insert(accounts.bob, &position(10_000, 7_000));
+
+            assert_eq!(
+                market.liquidate(accounts.bob, accounts.eve, 1_000),
+                Err(Error::PositionHealthy)
+            );
+        }
+    }
+}
@@ -0,0 +1,288 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::mock_receiver::{Instructions, MockReceiver, MockReceiverRef, NestedLoan, Repayment};
+
+/// A flash loan receiver used by the lender tests, which repays each loan as
+/// instructed by the loan data.
+#[ink::contract]
+mod mock_receiver {
+    use ierc20::IERC20;
+    use ierc3156::ierc3156_flash_borrower::{Error, IERC3156FlashBorrower, Result};
+    use ierc3156::ierc3156_flash_lender::{IERC3156FlashLender, Result as LenderResult};
+    use ierc3156::ierc3156_native_flash_borrower::IERC3156NativeFlashBorrower;
+    use ierc3156::ierc7399_flash_borrower::IERC7399FlashBorrower;
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::{hash::Keccak256, CallFlags};
+    use ink::prelude::vec::Vec;
+    use ink::scale::{Decode, Encode};
+
+    /// How the receiver pays back a loan.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum Repayment {
+        /// Approve the lender to pull `amount + fee`.
+        Approve,
+        /// Transfer `amount + fee` to the lender, approving nothing.
+        Transfer,
+        /// Transfer `amount + fee` minus the given shortfall to the lender,
+        /// approving nothing.
+        Short(u128),
+    }
+
+    /// A loan the receiver requests from the lender while holding another one.
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub struct NestedLoan {
+        /// The token to borrow.
+        pub token: AccountId,
+        /// The amount of tokens to borrow.
+        pub amount: u128,
+        /// How the nested loan is paid back.
+        pub repayment: Repayment,
+        /// The result the lender must return, failing the callback otherwise.
+        pub expected: LenderResult<bool>,
+    }
+
+    /// What the receiver does with a loan, SCALE-encoded as the loan data.
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub struct Instructions {
+        /// How the loan is paid back.
+        pub repayment: Repayment,
+        /// A loan requested before paying back, if any.
+        pub nested_loan: Option<NestedLoan>,
+    }
+
+    impl From<Repayment> for Instructions {
+        fn from(repayment: Repayment) -> Self {
+            Self {
+                repayment,
+                nested_loan: None,
+            }
+        }
+    }
+
+    #[ink(storage)]
+    pub struct MockReceiver {
+        /// The lender allowed to call back into the receiver.
+        lender: AccountId,
+    }
+
+    impl IERC3156FlashBorrower for MockReceiver {
+        /// ERC-3156 Flash loan callback.
+        ///
+        /// Takes the nested loan and repays the loan as told by the [`Instructions`]
+        /// encoded in `data`. Any initiator is accepted.
+        ///
+        /// ## Parameters:
+        /// - `initiator`: The account that initiated the loan.
+        /// - `token`: The address of the token that was lent.
+        /// - `amount`: The amount of tokens borrowed.
+        /// - `fee`: The fee charged by the lender.
+        /// - `data`: The encoded [`Instructions`].
+        ///
+        /// ## Returns:
+        /// - A `bool` hash signaling successful execution of the callback.
+        #[ink(message)]
+        fn on_flash_loan(
+            &self,
+            _initiator: AccountId,
+            token: AccountId,
+            amount: u128,
+            fee: u128,
+            data: Vec<u8>,
+        ) -> Result<[u8; 32]> {
+            if self.env().caller() != self.lender {
+                return Err(Error::UntrustedLender);
+            }
+            let instructions =
+                Instructions::decode(&mut &data[..]).map_err(|_| Error::ScaleDecodingErr)?;
+            if let Some(nested_loan) = instructions.nested_loan {
+                self.take_nested_loan(nested_loan)?;
+            }
+            Self::repay(instructions.repayment, self.lender, token, amount + fee)?;
+            Ok(self
+                .env()
+                .hash_bytes::<Keccak256>(b"ERC3156FlashBorrower.onFlashLoan"))
+        }
+
+        /// Requests a flash loan of `amount` of `token` from the lender, repaid as
+        /// told by the [`Instructions`] encoded in `data`.
+        ///
+        /// ## Parameters:
+        /// - `token`: The address of the token to borrow.
+        /// - `amount`: The amount of tokens to borrow.
+        /// - `data`: The encoded [`Instructions`].
+        /// - `min_profit`: Ignored, the receiver only pays the fee.
+        #[ink(message)]
+        fn flash_borrow(
+            &mut self,
+            token: AccountId,
+            amount: u128,
+            data: Vec<u8>,
+            _min_profit: Option<u128>,
+        ) -> Result<()> {
+            let mut lender: ink::contract_ref!(IERC3156FlashLender) = self.lender.into();
+            lender
+                .flash_loan(self.env().account_id(), token, amount, data)
+                .map_err(Error::ERC3156LenderError)?;
+            Ok(())
+        }
+    }
+
+    impl IERC3156NativeFlashBorrower for MockReceiver {
+        /// Native flash loan callback.
+        ///
+        /// Checks that the loan came with the call and repays it as told by the
+        /// [`Instructions`] encoded in `data`. The lender cannot pull native balance,
+        /// so only transfers repay it. Any initiator is accepted.
+        ///
+        /// ## Parameters:
+        /// - `initiator`: The account that initiated the loan.
+        /// - `amount`: The amount of native balance borrowed.
+        /// - `fee`: The fee charged by the lender.
+        /// - `data`: The encoded [`Instructions`].
+        ///
+        /// ## Returns:
+        /// - A `bool` hash signaling successful execution of the callback.
+        ///
+        /// ## Errors:
+        /// - `UnexpectedAmount` if the transferred value is not `amount`.
+        /// - `InvalidAction` if the instructions ask for an approval or a nested loan.
+        /// - `InsufficientRepayment` if the receiver cannot transfer the repayment.
+        #[ink(message, payable)]
+        fn on_native_flash_loan(
+            &mut self,
+            _initiator: AccountId,
+            amount: u128,
+            fee: u128,
+            data: Vec<u8>,
+        ) -> Result<[u8; 32]> {
+            if self.env().caller() != self.lender {
+                return Err(Error::UntrustedLender);
+            }
+            let received = self.env().transferred_value();
+            if received != amount {
+                return Err(Error::UnexpectedAmount {
+                    expected: amount,
+                    received,
+                });
+            }
+            let instructions =
+                Instructions::decode(&mut &data[..]).map_err(|_| Error::ScaleDecodingErr)?;
+            if instructions.nested_loan.is_some() {
+                return Err(Error::InvalidAction);
+            }
+            let owed = amount + fee;
+            let repayment = match instructions.repayment {
+                Repayment::Approve => return Err(Error::InvalidAction),
+                Repayment::Transfer => owed,
+                Repayment::Short(shortfall) => owed.saturating_sub(shortfall),
+            };
+            self.env().transfer(self.lender, repayment).map_err(|_| {
+                Error::InsufficientRepayment {
+                    balance: self.env().balance(),
+                    needed: repayment,
+                }
+            })?;
+            Ok(self
+                .env()
+                .hash_bytes::<Keccak256>(b"ERC3156NativeFlashBorrower.onNativeFlashLoan"))
+        }
+    }
+
+    impl IERC7399FlashBorrower for MockReceiver {
+        /// ERC-7399 Flash loan callback.
+        ///
+        /// Pays back `payment_receiver` as told by the [`Instructions`] encoded in
+        /// `data`, from the receiver's own balance. Any initiator is accepted.
+        ///
+        /// ## Parameters:
+        /// - `initiator`: The account that initiated the loan.
+        /// - `payment_receiver`: The account the loan must be paid back to.
+        /// - `asset`: The address of the token that was lent.
+        /// - `amount`: The amount of tokens borrowed.
+        /// - `fee`: The fee charged by the lender.
+        /// - `data`: The encoded [`Instructions`].
+        ///
+        /// ## Returns:
+        /// - `data`, unchanged.
+        #[ink(message)]
+        fn on_flash(
+            &self,
+            _initiator: AccountId,
+            payment_receiver: AccountId,
+            asset: AccountId,
+            amount: u128,
+            fee: u128,
+            data: Vec<u8>,
+        ) -> Result<Vec<u8>> {
+            if self.env().caller() != self.lender {
+                return Err(Error::UntrustedLender);
+            }
+            let instructions =
+                Instructions::decode(&mut &data[..]).map_err(|_| Error::ScaleDecodingErr)?;
+            if let Some(nested_loan) = instructions.nested_loan {
+                self.take_nested_loan(nested_loan)?;
+            }
+            Self::repay(
+                instructions.repayment,
+                payment_receiver,
+                asset,
+                amount + fee,
+            )?;
+            Ok(data)
+        }
+    }
+
+    impl MockReceiver {
+        /// Creates a new [`MockReceiver`] called back by `lender`, keeping any value
+        /// sent along to pay native loan fees.
+        #[ink(constructor, payable)]
+        pub fn new(lender: AccountId) -> Self {
+            Self { lender }
+        }
+
+        /// Pays `owed` of `token` back to `payment_receiver` as told by `repayment`.
+        fn repay(
+            repayment: Repayment,
+            payment_receiver: AccountId,
+            token: AccountId,
+            owed: u128,
+        ) -> Result<()> {
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            match repayment {
+                Repayment::Approve => erc20.approve(payment_receiver, owed),
+                Repayment::Transfer => erc20.transfer(payment_receiver, owed),
+                Repayment::Short(shortfall) => {
+                    erc20.transfer(payment_receiver, owed.saturating_sub(shortfall))
+                }
+            }
+            .map_err(Error::ERC20Error)?;
+            Ok(())
+        }
+
+        /// Borrows `nested_loan` from the lender while the current loan is held,
+        /// allowing the lender to call back into the receiver.
+        ///
+        /// ## Errors:
+        /// - `InvalidAction` if the lender does not return the expected result.
+        fn take_nested_loan(&self, nested_loan: NestedLoan) -> Result<()> {
+            let mut lender: ink::contract_ref!(IERC3156FlashLender) = self.lender.into();
+            let result = lender
+                .call_mut()
+                .flash_loan(
+                    self.env().account_id(),
+                    nested_loan.token,
+                    nested_loan.amount,
+                    Instructions::from(nested_loan.repayment).encode(),
+                )
+                .call_flags(CallFlags::ALLOW_REENTRY)
+                .invoke();
+            if result != nested_loan.expected {
+                return Err(Error::InvalidAction);
+            }
+            Ok(())
+        }
+    }
+}
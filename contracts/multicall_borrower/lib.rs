@@ -0,0 +1,651 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::multicall_borrower::{MulticallBorrower, MulticallBorrowerRef, PendingLoan, RawCall};
+
+#[ink::contract]
+mod multicall_borrower {
+    use ierc20::IERC20;
+    use ierc3156::ierc3156_flash_borrower::{Error, IERC3156FlashBorrower, Result};
+    use ierc3156::ierc3156_flash_lender::IERC3156FlashLender;
+    use ink::codegen::TraitCallBuilder;
+    use ink::env::call::{build_call, ExecutionInput, Selector};
+    use ink::env::{hash::Keccak256, CallFlags};
+    use ink::prelude::vec;
+    use ink::prelude::vec::Vec;
+    use ink::scale::{Decode, Encode, Input, Output};
+    use ink::storage::Lazy;
+
+    /// A cross-contract call executed while the borrower holds the loan.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub struct RawCall {
+        /// The contract to call.
+        pub callee: AccountId,
+        /// The selector of the message to call.
+        pub selector: [u8; 4],
+        /// The SCALE-encoded message arguments.
+        pub input: Vec<u8>,
+        /// The native balance transferred with the call.
+        pub value: Balance,
+    }
+
+    /// Already SCALE-encoded call arguments, written as is.
+    struct CallInput<'a>(&'a [u8]);
+
+    impl Encode for CallInput<'_> {
+        fn size_hint(&self) -> usize {
+            self.0.len()
+        }
+
+        fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+            dest.write(self.0);
+        }
+    }
+
+    /// Raw call output, accepting whatever the callee returned.
+    struct CallOutput;
+
+    impl Decode for CallOutput {
+        fn decode<I: Input>(input: &mut I) -> core::result::Result<Self, ink::scale::Error> {
+            let mut output = vec![0; input.remaining_len()?.unwrap_or_default()];
+            input.read(&mut output)?;
+            Ok(Self)
+        }
+    }
+
+    /// The loan the borrower is waiting the lender to call back for.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+    pub struct PendingLoan {
+        pub token: AccountId,
+        pub amount: Balance,
+        pub fee: Balance,
+        /// Keccak-256 hash of the encoded calls sent with the loan.
+        pub data_hash: [u8; 32],
+    }
+
+    #[ink(storage)]
+    pub struct MulticallBorrower {
+        /// Stores the receiver lender's AccountId.
+        lender: AccountId,
+        /// Account allowed to request loans and move the borrower funds.
+        owner: AccountId,
+        /// The loan requested by `flash_borrow_with_calls`, while the lender runs.
+        pending_loan: Lazy<Option<PendingLoan>>,
+    }
+
+    impl IERC3156FlashBorrower for MulticallBorrower {
+        /// ERC-3156 Flash loan callback.
+        ///
+        /// This function is called by the lender after the tokens have been
+        /// transferred. It verifies the caller and initiator, executes the calls
+        /// encoded in `data` in order, and approves the lender to pull the repayment.
+        ///
+        /// ## Parameters:
+        /// - `initiator`: The account that initiated the loan. Must be `self`.
+        /// - `token`: The address of the token that was lent.
+        /// - `amount`: The amount of tokens borrowed.
+        /// - `fee`: The fee charged by the lender.
+        /// - `data`: The encoded `Vec<RawCall>` to execute.
+        ///
+        /// ## Returns:
+        /// - A `bool` hash signaling successful execution of the callback.
+        #[ink(message)]
+        fn on_flash_loan(
+            &self,
+            initiator: AccountId,
+            token: AccountId,
+            amount: Balance,
+            fee: Balance,
+            data: Vec<u8>,
+        ) -> Result<[u8; 32]> {
+            if self.env().caller() != self.lender {
+                return Err(Error::UntrustedLender);
+            }
+            if initiator != self.env().account_id() {
+                return Err(Error::UntrustedLoanInitiator);
+            }
+            self.ensure_pending_loan(token, amount, fee, &data)?;
+
+            let calls =
+                Vec::<RawCall>::decode(&mut &data[..]).map_err(|_| Error::ScaleDecodingErr)?;
+            for (index, call) in calls.iter().enumerate() {
+                self.execute_call(index as u32, call)?;
+            }
+
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            let repayment = amount.checked_add(fee).ok_or(Error::Overflow)?;
+            let balance = erc20.balance_of(self.env().account_id());
+            if balance < repayment {
+                return Err(Error::InsufficientRepayment {
+                    balance,
+                    needed: repayment,
+                });
+            }
+            erc20
+                .approve(self.lender, repayment)
+                .map_err(Error::ERC20Error)?;
+
+            Ok(self
+                .env()
+                .hash_bytes::<Keccak256>(b"ERC3156FlashBorrower.onFlashLoan"))
+        }
+
+        /// Initiates a flash loan from the trusted lender that executes the calls
+        /// encoded in `data`.
+        ///
+        /// ## Parameters:
+        /// - `token`: The address of the token to borrow.
+        /// - `amount`: The amount of tokens to borrow.
+        /// - `data`: The encoded `Vec<RawCall>` to execute. Empty data executes no calls.
+        /// - `min_profit`: The minimum gain in `token` the loan must produce, or `None` to
+        ///   accept any loss.
+        #[ink(message)]
+        fn flash_borrow(
+            &mut self,
+            token: AccountId,
+            amount: u128,
+            data: Vec<u8>,
+            min_profit: Option<u128>,
+        ) -> Result<()> {
+            let calls = if data.is_empty() {
+                Vec::new()
+            } else {
+                Vec::<RawCall>::decode(&mut &data[..]).map_err(|_| Error::ScaleDecodingErr)?
+            };
+            self.flash_borrow_with_calls(token, amount, calls, min_profit)
+        }
+    }
+
+    impl MulticallBorrower {
+        /// Creates a new [`MulticallBorrower`] owned by the caller.
+        ///
+        /// ## Parameters:
+        /// - `lender`: The trusted flash lender contract.
+        #[ink(constructor)]
+        pub fn new(lender: AccountId) -> Self {
+            Self {
+                lender,
+                owner: Self::env().caller(),
+                pending_loan: Lazy::default(),
+            }
+        }
+
+        /// Returns the current owner.
+        #[ink(message)]
+        pub fn owner(&self) -> AccountId {
+            self.owner
+        }
+
+        /// Initiates a flash loan from the trusted lender that executes `calls`
+        /// in order before repaying.
+        ///
+        /// The loan is recorded as pending while the lender runs, so that the callback
+        /// only accepts the exact `token`, `amount`, quoted fee and `calls`. The allowance
+        /// granted to the lender for the repayment is reset to zero after the loan.
+        ///
+        /// ## Parameters:
+        /// - `token`: The address of the token to borrow.
+        /// - `amount`: The amount of tokens to borrow.
+        /// - `calls`: The calls executed with the borrowed funds.
+        /// - `min_profit`: The minimum gain in `token` the loan must produce, or `None` to
+        ///   accept any loss.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        /// - `InsufficientProfit` if the loan gained less than `min_profit`.
+        #[ink(message)]
+        pub fn flash_borrow_with_calls(
+            &mut self,
+            token: AccountId,
+            amount: u128,
+            calls: Vec<RawCall>,
+            min_profit: Option<u128>,
+        ) -> Result<()> {
+            self.ensure_owner()?;
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            let balance_before = erc20.balance_of(self.env().account_id());
+            let mut lender: ink::contract_ref!(IERC3156FlashLender) = self.lender.into();
+            let fee = lender
+                .flash_fee(token, amount)
+                .map_err(Error::ERC3156LenderError)?;
+            let data = calls.encode();
+            self.pending_loan.set(&Some(PendingLoan {
+                token,
+                amount,
+                fee,
+                data_hash: self.env().hash_bytes::<Keccak256>(&data),
+            }));
+            // The lender calls back into `on_flash_loan`, so reentry must be allowed.
+            lender
+                .call_mut()
+                .flash_loan(self.env().account_id(), token, amount, data)
+                .call_flags(CallFlags::ALLOW_REENTRY)
+                .invoke()
+                .map_err(Error::ERC3156LenderError)?;
+            self.pending_loan.set(&None);
+            erc20.approve(self.lender, 0).map_err(Error::ERC20Error)?;
+            if let Some(min_profit) = min_profit {
+                let balance = erc20.balance_of(self.env().account_id());
+                let needed = balance_before.saturating_add(min_profit);
+                if balance < needed {
+                    return Err(Error::InsufficientProfit { balance, needed });
+                }
+            }
+            Ok(())
+        }
+
+        /// Transfers `amount` of `token` held by the borrower to `to`.
+        ///
+        /// ## Parameters:
+        /// - `token`: The token to withdraw.
+        /// - `amount`: The amount to withdraw.
+        /// - `to`: The recipient of the tokens.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        /// - `ERC20Error` if the transfer fails.
+        #[ink(message)]
+        pub fn withdraw(&mut self, token: AccountId, amount: Balance, to: AccountId) -> Result<()> {
+            self.ensure_owner()?;
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            erc20.transfer(to, amount).map_err(Error::ERC20Error)?;
+            Ok(())
+        }
+
+        /// Transfers the whole `token` balance of the borrower to the owner.
+        ///
+        /// ## Parameters:
+        /// - `token`: The token to sweep.
+        ///
+        /// ## Returns:
+        /// - The amount of tokens swept.
+        ///
+        /// ## Errors:
+        /// - `Unauthorized` if the caller is not the owner.
+        /// - `ERC20Error` if the transfer fails.
+        #[ink(message)]
+        pub fn sweep(&mut self, token: AccountId) -> Result<Balance> {
+            self.ensure_owner()?;
+            let mut erc20: ink::contract_ref!(IERC20) = token.into();
+            let amount = erc20.balance_of(self.env().account_id());
+            erc20
+                .transfer(self.owner, amount)
+                .map_err(Error::ERC20Error)?;
+            Ok(amount)
+        }
+
+        /// Returns `Unauthorized` unless the caller is the owner.
+        fn ensure_owner(&self) -> Result<()> {
+            if self.env().caller() != self.owner {
+                return Err(Error::Unauthorized);
+            }
+            Ok(())
+        }
+
+        /// Checks that a loan callback matches the loan requested by
+        /// `flash_borrow_with_calls`.
+        fn ensure_pending_loan(
+            &self,
+            token: AccountId,
+            amount: Balance,
+            fee: Balance,
+            data: &[u8],
+        ) -> Result<()> {
+            let pending = self
+                .pending_loan
+                .get()
+                .flatten()
+                .ok_or(Error::NoPendingLoan)?;
+            if token != pending.token {
+                return Err(Error::UnexpectedToken {
+                    expected: pending.token,
+                    received: token,
+                });
+            }
+            if amount != pending.amount {
+                return Err(Error::UnexpectedAmount {
+                    expected: pending.amount,
+                    received: amount,
+                });
+            }
+            if fee != pending.fee {
+                return Err(Error::UnexpectedFee {
+                    expected: pending.fee,
+                    received: fee,
+                });
+            }
+            if self.env().hash_bytes::<Keccak256>(data) != pending.data_hash {
+                return Err(Error::UnexpectedData);
+            }
+            Ok(())
+        }
+
+        /// Executes `call`, the `index`-th of the loan, ignoring its output.
+        ///
+        /// Only failures of the call itself are reported: a message returning an
+        /// `Err` value is seen as successful, so the repayment check after the
+        /// calls is what ultimately guards the loan.
+        fn execute_call(&self, index: u32, call: &RawCall) -> Result<()> {
+            build_call::<Environment>()
+                .call(call.callee)
+                .transferred_value(call.value)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(call.selector))
+                        .push_arg(CallInput(&call.input)),
+                )
+                .returns::<CallOutput>()
+                .try_invoke()
+                .map_err(|_| Error::CallFailed(index))?
+                .map_err(|_| Error::CallFailed(index))?;
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn accounts() -> ink::env::test::DefaultAccounts<Environment> {
+            ink::env::test::default_accounts::<Environment>()
+        }
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<Environment>(caller);
+        }
+
+        /// Deploys a borrower trusting `bob` as lender and returns it with its own account.
+        fn borrower() -> (MulticallBorrower, AccountId) {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let borrower = MulticallBorrower::new(accounts.bob);
+            (borrower, ink::env::account_id::<Environment>())
+        }
+
+        fn data_hash(data: &[u8]) -> [u8; 32] {
+            let mut output = [0u8; 32];
+            ink::env::hash_bytes::<Keccak256>(data, &mut output);
+            output
+        }
+
+        #[ink::test]
+        fn callbacks_are_accepted_only_from_the_lender() {
+            let accounts = accounts();
+            let (borrower, this) = borrower();
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                borrower.on_flash_loan(this, accounts.django, 100, 1, Vec::new()),
+                Err(Error::UntrustedLender)
+            );
+        }
+
+        #[ink::test]
+        fn callbacks_are_accepted_only_for_own_loans() {
+            let accounts = accounts();
+            let (borrower, _) = borrower();
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_flash_loan(accounts.charlie, accounts.django, 100, 1, Vec::new()),
+                Err(Error::UntrustedLoanInitiator)
+            );
+        }
+
+        #[ink::test]
+        fn callback_without_pending_loan_is_rejected() {
+            let accounts = accounts();
+            let (borrower, this) = borrower();
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_flash_loan(this, accounts.django, 100, 1, Vec::new()),
+                Err(Error::NoPendingLoan)
+            );
+        }
+
+        #[ink::test]
+        fn callback_must_match_pending_loan() {
+            let accounts = accounts();
+            let (mut borrower, this) = borrower();
+            let token = accounts.django;
+            borrower.pending_loan.set(&Some(PendingLoan {
+                token,
+                amount: 100,
+                fee: 1,
+                data_hash: data_hash(&Vec::<RawCall>::new().encode()),
+            }));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_flash_loan(this, accounts.eve, 100, 1, Vec::new()),
+                Err(Error::UnexpectedToken {
+                    expected: token,
+                    received: accounts.eve,
+                })
+            );
+            assert_eq!(
+                borrower.on_flash_loan(this, token, 200, 1, Vec::new()),
+                Err(Error::UnexpectedAmount {
+                    expected: 100,
+                    received: 200,
+                })
+            );
+            assert_eq!(
+                borrower.on_flash_loan(this, token, 100, 0, Vec::new()),
+                Err(Error::UnexpectedFee {
+                    expected: 1,
+                    received: 0,
+                })
+            );
+        }
+
+        #[ink::test]
+        fn callback_must_carry_the_requested_calls() {
+            let accounts = accounts();
+            let (mut borrower, this) = borrower();
+            let token = accounts.django;
+            borrower.pending_loan.set(&Some(PendingLoan {
+                token,
+                amount: 100,
+                fee: 1,
+                data_hash: data_hash(&Vec::<RawCall>::new().encode()),
+            }));
+
+            // A lender replaying the callback cannot swap in calls of its own.
+            let replayed = vec![RawCall {
+                callee: token,
+                selector: ink::selector_bytes!("IERC20::transfer"),
+                input: (accounts.bob, 100u128).encode(),
+                value: 0,
+            }];
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_flash_loan(this, token, 100, 1, replayed.encode()),
+                Err(Error::UnexpectedData)
+            );
+        }
+
+        #[ink::test]
+        fn huge_fee_is_rejected_without_trapping() {
+            let accounts = accounts();
+            let (mut borrower, this) = borrower();
+            let token = accounts.django;
+            let data = Vec::<RawCall>::new().encode();
+            borrower.pending_loan.set(&Some(PendingLoan {
+                token,
+                amount: 100,
+                fee: Balance::MAX,
+                data_hash: data_hash(&data),
+            }));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_flash_loan(this, token, 100, Balance::MAX, data),
+                Err(Error::Overflow)
+            );
+        }
+
+        #[ink::test]
+        fn malformed_calls_are_rejected() {
+            let accounts = accounts();
+            let (mut borrower, this) = borrower();
+            let token = accounts.django;
+            borrower.pending_loan.set(&Some(PendingLoan {
+                token,
+                amount: 100,
+                fee: 1,
+                data_hash: data_hash(&[0xff]),
+            }));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.on_flash_loan(this, token, 100, 1, vec![0xff]),
+                Err(Error::ScaleDecodingErr)
+            );
+            set_caller(accounts.alice);
+            assert_eq!(
+                borrower.flash_borrow(token, 100, vec![0xff], None),
+                Err(Error::ScaleDecodingErr)
+            );
+        }
+
+        #[ink::test]
+        fn only_owner_moves_funds() {
+            let accounts = accounts();
+            let (mut borrower, _) = borrower();
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                borrower.flash_borrow_with_calls(accounts.django, 100, Vec::new(), None),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(
+                borrower.withdraw(accounts.django, 100, accounts.bob),
+                Err(Error::Unauthorized)
+            );
+            assert_eq!(borrower.sweep(accounts.django), Err(Error::Unauthorized));
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use erc20::{Erc20, Erc20Ref};
+        use flash_lender::FlashLenderRef;
+        use ink_e2e::ContractsBackend;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        #[ink_e2e::test]
+        async fn flash_borrow_with_calls_executes_calls<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let bob = ink_e2e::account_id(ink_e2e::AccountKeyring::Bob);
+            let mut erc20_constructor = Erc20Ref::new(1_000_000);
+            let erc20 = client
+                .instantiate("erc20", &ink_e2e::alice(), &mut erc20_constructor)
+                .submit()
+                .await
+                .expect("erc20 instantiate failed");
+            let mut erc20_call = erc20.call_builder::<Erc20>();
+
+            let mut lender_constructor = FlashLenderRef::new(vec![erc20.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+
+            let mut borrower_constructor = MulticallBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "multicall_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("multicall_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<MulticallBorrower>();
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(lender.account_id, 500_000),
+                )
+                .submit()
+                .await
+                .expect("lender funding failed");
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.transfer(borrower.account_id, 1_000),
+                )
+                .submit()
+                .await
+                .expect("borrower funding failed");
+
+            let calls = vec![RawCall {
+                callee: erc20.account_id,
+                selector: ink::selector_bytes!("IERC20::transfer"),
+                input: (bob, 50u128).encode(),
+                value: 0,
+            }];
+            let unauthorized = client
+                .call(
+                    &ink_e2e::bob(),
+                    &borrower_call.flash_borrow_with_calls(
+                        erc20.account_id,
+                        100_000,
+                        calls.clone(),
+                        None,
+                    ),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(unauthorized.return_value(), Err(Error::Unauthorized));
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow_with_calls(erc20.account_id, 100_000, calls, None),
+                )
+                .submit()
+                .await
+                .expect("flash_borrow_with_calls failed");
+
+            let bob_balance = client
+                .call(&ink_e2e::alice(), &erc20_call.balance_of(bob))
+                .dry_run()
+                .await?;
+            assert_eq!(bob_balance.return_value(), 50);
+            let borrower_balance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.balance_of(borrower.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(borrower_balance.return_value(), 850);
+            let allowance = client
+                .call(
+                    &ink_e2e::alice(),
+                    &erc20_call.allowance(borrower.account_id, lender.account_id),
+                )
+                .dry_run()
+                .await?;
+            assert_eq!(allowance.return_value(), 0);
+
+            let swept = client
+                .call(&ink_e2e::alice(), &borrower_call.sweep(erc20.account_id))
+                .submit()
+                .await
+                .expect("sweep failed");
+            assert_eq!(swept.return_value(), Ok(850));
+
+            Ok(())
+        }
+    }
+}
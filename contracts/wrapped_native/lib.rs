@@ -0,0 +1,320 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::wrapped_native::{WrapError, WrappedNative, WrappedNativeRef};
+
+#[ink::contract]
+mod wrapped_native {
+    use ierc20::{Erc20Core, Error, Result, IERC20};
+
+    /// The result type of wrapping and unwrapping.
+    pub type WrapResult<T> = core::result::Result<T, WrapError>;
+
+    /// The wrapping error types.
+    #[derive(Debug, PartialEq, Eq)]
+    #[ink::scale_derive(Encode, Decode, TypeInfo)]
+    pub enum WrapError {
+        /// Returned if sending the native balance failed.
+        TransferFailed,
+        /// Error related to ERC20.
+        ERC20Error(Error),
+    }
+
+    /// An ERC20 token backed 1:1 by the native balance it holds.
+    #[ink(storage)]
+    pub struct WrappedNative {
+        /// Balances, allowances and supply of the token.
+        token: Erc20Core,
+    }
+
+    /// Emitted when native balance is wrapped into tokens.
+    #[ink(event)]
+    pub struct Deposit {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
+
+    /// Emitted when tokens are unwrapped into native balance.
+    #[ink(event)]
+    pub struct Withdrawal {
+        #[ink(topic)]
+        account: AccountId,
+        amount: Balance,
+    }
+
+    impl Default for WrappedNative {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl IERC20 for WrappedNative {
+        /// Returns the total token supply.
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self.token.total_supply()
+        }
+
+        /// Returns the balance of the given `account`.
+        #[ink(message)]
+        fn balance_of(&self, account: AccountId) -> Balance {
+            self.token.balance_of(account)
+        }
+
+        /// Transfers `value` tokens from the caller's account to `to`.
+        ///
+        /// Returns `true` if the operation succeeded.
+        ///
+        /// Emits a `Transfer` event.
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, value: Balance) -> Result<bool> {
+            self.token.transfer(self.env().caller(), to, value)?;
+            Ok(true)
+        }
+
+        /// Returns the remaining number of tokens that `spender` can spend
+        /// on behalf of `owner` through `transfer_from`.
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.token.allowance(owner, spender)
+        }
+
+        /// Sets `value` as the allowance of `spender` over the caller’s tokens.
+        ///
+        /// Returns `true` if the operation succeeded.
+        ///
+        /// Emits an `Approval` event.
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<bool> {
+            self.token.approve(self.env().caller(), spender, value)?;
+            Ok(true)
+        }
+
+        /// Transfers `value` tokens from `from` to `to` using the allowance mechanism.
+        /// `value` is then deducted from the caller’s allowance.
+        ///
+        /// Returns `true` if the operation succeeded.
+        ///
+        /// Emits a `Transfer` event.
+        #[ink(message)]
+        fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<bool> {
+            self.token
+                .transfer_from(self.env().caller(), from, to, value)?;
+            Ok(true)
+        }
+    }
+
+    impl WrappedNative {
+        /// Creates a new [`WrappedNative`] with no supply.
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                token: Erc20Core::default(),
+            }
+        }
+
+        /// Mints to the caller as many tokens as the native balance attached to the call.
+        ///
+        /// ## Returns:
+        /// - The amount of tokens minted.
+        ///
+        /// Emits a `Deposit` event.
+        #[ink(message, payable)]
+        pub fn deposit(&mut self) -> WrapResult<Balance> {
+            let account = self.env().caller();
+            let amount = self.env().transferred_value();
+            self.token
+                .mint(account, amount)
+                .map_err(WrapError::ERC20Error)?;
+            self.env().emit_event(Deposit { account, amount });
+            Ok(amount)
+        }
+
+        /// Burns `amount` tokens of the caller and sends back as much native balance.
+        ///
+        /// ## Params:
+        /// - `amount`: The amount of tokens burned.
+        ///
+        /// ## Errors:
+        /// - `ERC20Error` if the caller holds less than `amount`.
+        /// - `TransferFailed` if the native balance cannot be sent to the caller.
+        ///
+        /// Emits a `Withdrawal` event.
+        #[ink(message)]
+        pub fn withdraw(&mut self, amount: Balance) -> WrapResult<()> {
+            let account = self.env().caller();
+            self.token
+                .burn(account, amount)
+                .map_err(WrapError::ERC20Error)?;
+            self.env()
+                .transfer(account, amount)
+                .map_err(|_| WrapError::TransferFailed)?;
+            self.env().emit_event(Withdrawal { account, amount });
+            Ok(())
+        }
+    }
+
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        use super::*;
+        use flash_borrower::{FlashBorrower, FlashBorrowerRef};
+        use flash_lender::FlashLenderRef;
+        use ierc3156::ierc3156_flash_borrower::IERC3156FlashBorrower;
+        use ink_e2e::ContractsBackend;
+
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        #[ink_e2e::test]
+        async fn wrapped_native_is_flash_lent_like_any_token<Client: E2EBackend>(
+            mut client: Client,
+        ) -> E2EResult<()> {
+            let mut token_constructor = WrappedNativeRef::new();
+            let token = client
+                .instantiate("wrapped_native", &ink_e2e::alice(), &mut token_constructor)
+                .submit()
+                .await
+                .expect("wrapped_native instantiate failed");
+            let mut token_call = token.call_builder::<WrappedNative>();
+            let deposit = client
+                .call(&ink_e2e::alice(), &token_call.deposit())
+                .value(1_000_000)
+                .submit()
+                .await
+                .expect("deposit failed");
+            assert_eq!(deposit.return_value(), Ok(1_000_000));
+
+            let mut lender_constructor = FlashLenderRef::new(vec![token.account_id], 10);
+            let lender = client
+                .instantiate("flash_lender", &ink_e2e::alice(), &mut lender_constructor)
+                .submit()
+                .await
+                .expect("flash_lender instantiate failed");
+            let mut borrower_constructor = FlashBorrowerRef::new(lender.account_id);
+            let borrower = client
+                .instantiate(
+                    "flash_borrower",
+                    &ink_e2e::alice(),
+                    &mut borrower_constructor,
+                )
+                .submit()
+                .await
+                .expect("flash_borrower instantiate failed");
+            let mut borrower_call = borrower.call_builder::<FlashBorrower>();
+            for (account, amount) in [(lender.account_id, 500_000), (borrower.account_id, 1_000)] {
+                client
+                    .call(&ink_e2e::alice(), &token_call.transfer(account, amount))
+                    .submit()
+                    .await
+                    .expect("transfer failed");
+            }
+
+            client
+                .call(
+                    &ink_e2e::alice(),
+                    &borrower_call.flash_borrow(token.account_id, 100_000, Vec::new(), None),
+                )
+                .submit()
+                .await
+                .expect("flash_borrow failed");
+            let lender_balance = client
+                .call(&ink_e2e::alice(), &token_call.balance_of(lender.account_id))
+                .dry_run()
+                .await?;
+            assert_eq!(lender_balance.return_value(), 500_100);
+
+            // Unwrapping burns the tokens and leaves the supply backed by the rest.
+            client
+                .call(&ink_e2e::alice(), &token_call.withdraw(400_000))
+                .submit()
+                .await
+                .expect("withdraw failed");
+            let total_supply = client
+                .call(&ink_e2e::alice(), &token_call.total_supply())
+                .dry_run()
+                .await?;
+            assert_eq!(total_supply.return_value(), 600_000);
+            let too_much = client
+                .call(&ink_e2e::alice(), &token_call.withdraw(100_000))
+                .dry_run()
+                .await?;
+            assert_eq!(
+                too_much.return_value(),
+                Err(WrapError::ERC20Error(Error::InsufficientBalance {
+                    sender: ink_e2e::account_id(ink_e2e::AccountKeyring::Alice),
+                    balance: 99_000,
+                    needed: 100_000,
+                }))
+            );
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn accounts() -> ink::env::test::DefaultAccounts<Environment> {
+            ink::env::test::default_accounts::<Environment>()
+        }
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<Environment>(caller);
+        }
+
+        /// Calls `deposit` from the current caller with `amount` attached.
+        fn deposit(token: &mut WrappedNative, amount: Balance) -> WrapResult<Balance> {
+            let contract = ink::env::account_id::<Environment>();
+            let balance = ink::env::balance::<Environment>();
+            ink::env::test::set_account_balance::<Environment>(contract, balance + amount);
+            ink::env::test::set_value_transferred::<Environment>(amount);
+            let minted = token.deposit();
+            ink::env::test::set_value_transferred::<Environment>(0);
+            minted
+        }
+
+        #[ink::test]
+        fn deposit_mints_the_attached_value() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut token = WrappedNative::new();
+
+            assert_eq!(deposit(&mut token, 1_000), Ok(1_000));
+            assert_eq!(token.balance_of(accounts.alice), 1_000);
+            assert_eq!(token.total_supply(), 1_000);
+            assert_eq!(token.transfer(accounts.bob, 400), Ok(true));
+            assert_eq!(token.balance_of(accounts.bob), 400);
+        }
+
+        #[ink::test]
+        fn withdraw_burns_and_returns_native_balance() {
+            let accounts = accounts();
+            set_caller(accounts.alice);
+            let mut token = WrappedNative::new();
+            deposit(&mut token, 1_000).unwrap();
+            let alice_balance =
+                ink::env::test::get_account_balance::<Environment>(accounts.alice).unwrap();
+
+            assert_eq!(token.withdraw(300), Ok(()));
+            assert_eq!(token.balance_of(accounts.alice), 700);
+            assert_eq!(token.total_supply(), 700);
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(accounts.alice),
+                Ok(alice_balance + 300)
+            );
+            assert_eq!(
+                token.withdraw(701),
+                Err(WrapError::ERC20Error(Error::InsufficientBalance {
+                    sender: accounts.alice,
+                    balance: 700,
+                    needed: 701,
+                }))
+            );
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Trait definitions for a constant-product AMM pair and its flash swap callee.
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use ierc20::Error as ERC20Error;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+/// The AMM result type.
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// A trait for trading against a constant-product pair of two ERC20 tokens.
+#[ink::trait_definition]
+pub trait IPair {
+    /// Returns the first token of the pair.
+    #[ink(message)]
+    fn token_0(&self) -> AccountId;
+
+    /// Returns the second token of the pair.
+    #[ink(message)]
+    fn token_1(&self) -> AccountId;
+
+    /// Returns the reserves of `token_0` and `token_1`, in that order.
+    #[ink(message)]
+    fn get_reserves(&self) -> (u128, u128);
+
+    /// Returns the amount of the other token received for `amount_in` of `token_in`,
+    /// after the swap fee.
+    ///
+    /// ## Errors:
+    /// - `InvalidToken` if `token_in` is not part of the pair.
+    #[ink(message)]
+    fn get_amount_out(&self, token_in: AccountId, amount_in: u128) -> Result<u128>;
+
+    /// Swaps `amount_in` of `token_in`, pulled from the caller, for the other token.
+    ///
+    /// The caller must have approved the pair to spend `amount_in` of `token_in`.
+    ///
+    /// ## Parameters:
+    /// - `token_in`: The token sold.
+    /// - `amount_in`: The amount of `token_in` sold.
+    /// - `min_amount_out`: The minimum amount of the other token to receive.
+    /// - `to`: The recipient of the bought tokens.
+    ///
+    /// ## Returns:
+    /// - The amount of the other token sent to `to`.
+    #[ink(message)]
+    fn swap(
+        &mut self,
+        token_in: AccountId,
+        amount_in: u128,
+        min_amount_out: u128,
+        to: AccountId,
+    ) -> Result<u128>;
+
+    /// Optimistically sends `amount_0_out` of `token_0` and `amount_1_out` of
+    /// `token_1` to `to`, then checks that enough tokens were paid back to the pair
+    /// to keep the product of the reserves, net of the swap fee, from decreasing.
+    ///
+    /// When `data` is not empty, `to` is called through `IFlashSwapCallee::on_flash_swap`
+    /// after receiving the tokens and must pay the pair back before returning.
+    ///
+    /// ## Parameters:
+    /// - `amount_0_out`: The amount of `token_0` sent to `to`.
+    /// - `amount_1_out`: The amount of `token_1` sent to `to`.
+    /// - `to`: The recipient of the tokens.
+    /// - `data`: Arbitrary data forwarded to the callback.
+    #[ink(message)]
+    fn flash_swap(
+        &mut self,
+        amount_0_out: u128,
+        amount_1_out: u128,
+        to: AccountId,
+        data: Vec<u8>,
+    ) -> Result<()>;
+}
+
+/// A trait for receiving the tokens of a flash swap.
+#[ink::trait_definition]
+pub trait IFlashSwapCallee {
+    /// Flash swap callback.
+    ///
+    /// Called by the pair after sending the tokens to the callee. The pair must be
+    /// paid back before returning.
+    ///
+    /// ## Parameters:
+    /// - `initiator`: The account that requested the flash swap.
+    /// - `amount_0`: The amount of `token_0` received.
+    /// - `amount_1`: The amount of `token_1` received.
+    /// - `data`: The data passed to `flash_swap`.
+    #[ink(message)]
+    fn on_flash_swap(
+        &mut self,
+        initiator: AccountId,
+        amount_0: u128,
+        amount_1: u128,
+        data: Vec<u8>,
+    ) -> Result<()>;
+}
+
+/// The AMM error types.
+#[derive(Debug, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum Error {
+    /// Returned if the token is not part of the pair.
+    InvalidToken,
+    /// Returned if the recipient is one of the pair tokens.
+    InvalidTo,
+    /// Returned if no tokens were sold to the pair.
+    InsufficientInputAmount,
+    /// Returned if the swap would not send any tokens.
+    InsufficientOutputAmount,
+    /// Returned if the pair does not hold enough tokens for the swap.
+    InsufficientLiquidity,
+    /// Returned if adding liquidity would mint no liquidity, or less than requested.
+    InsufficientLiquidityMinted,
+    /// Returned if removing liquidity would return no tokens, or less than requested.
+    InsufficientLiquidityBurned,
+    /// Returned if the swap would send less than `min_amount_out`.
+    SlippageExceeded {
+        amount_out: u128,
+        min_amount_out: u128,
+    },
+    /// Returned if a flash swap decreased the product of the reserves.
+    KInvariant,
+    /// Returned if an arithmetic operation overflowed.
+    Overflow,
+    /// Returned if the flash swap callback failed.
+    CallbackFailed,
+    /// Error related to ERC20.
+    ERC20Error(ERC20Error),
+}
@@ -0,0 +1,188 @@
+//! ERC-20 accounting shared by the token contracts implementing `IERC20`.
+
+use crate::{Approval, Error, Result, Transfer};
+use ink::env::DefaultEnvironment;
+use ink::primitives::AccountId;
+use ink::storage::{Lazy, Mapping};
+
+/// Balances, allowances and supply of an ERC-20 token.
+///
+/// A token contract keeps it in its storage and forwards its `IERC20` messages
+/// to it, passing the caller where the standard uses `msg.sender`. Every change
+/// emits the matching `Transfer` or `Approval` event.
+///
+/// Every field is written to storage as soon as it changes, so a contract called
+/// back during one of its own messages, e.g. by a flash loan receiver, reads the
+/// up-to-date supply and balances.
+#[ink::storage_item]
+#[derive(Debug, Default)]
+pub struct Erc20Core {
+    /// Total token supply.
+    total_supply: Lazy<u128>,
+    /// Token balance of each account.
+    balances: Mapping<AccountId, u128>,
+    /// Allowance granted by an owner (first) to a spender (second).
+    allowances: Mapping<(AccountId, AccountId), u128>,
+}
+
+impl Erc20Core {
+    /// Returns the total token supply.
+    pub fn total_supply(&self) -> u128 {
+        self.total_supply.get().unwrap_or_default()
+    }
+
+    /// Returns the balance of the given `account`.
+    pub fn balance_of(&self, account: AccountId) -> u128 {
+        self.balances.get(account).unwrap_or_default()
+    }
+
+    /// Returns the remaining number of tokens that `spender` can spend
+    /// on behalf of `owner` through `transfer_from`.
+    pub fn allowance(&self, owner: AccountId, spender: AccountId) -> u128 {
+        self.allowances.get((owner, spender)).unwrap_or_default()
+    }
+
+    /// Moves `value` tokens from `from` to `to`.
+    ///
+    /// ## Errors:
+    /// - `InvalidSender` if `from` is the zero account.
+    /// - `InvalidReceiver` if `to` is the zero account.
+    /// - `InsufficientBalance` if `from` holds less than `value`.
+    pub fn transfer(&mut self, from: AccountId, to: AccountId, value: u128) -> Result<()> {
+        if from == Self::zero_account() {
+            return Err(Error::InvalidSender { sender: from });
+        }
+        if to == Self::zero_account() {
+            return Err(Error::InvalidReceiver { receiver: to });
+        }
+        let from_balance = self.balance_of(from);
+        if from_balance < value {
+            return Err(Error::InsufficientBalance {
+                sender: from,
+                balance: from_balance,
+                needed: value,
+            });
+        }
+        self.balances.insert(from, &(from_balance - value));
+        let to_balance = self.balance_of(to);
+        self.balances.insert(to, &(to_balance + value));
+        ink::env::emit_event::<DefaultEnvironment, _>(Transfer { from, to, value });
+        Ok(())
+    }
+
+    /// Moves `value` tokens from `from` to `to` on behalf of `spender`, deducting
+    /// them from its allowance.
+    ///
+    /// ## Errors:
+    /// - `InsufficientAllowance` if the allowance is lower than `value`.
+    /// - Any error of [`Erc20Core::transfer`].
+    pub fn transfer_from(
+        &mut self,
+        spender: AccountId,
+        from: AccountId,
+        to: AccountId,
+        value: u128,
+    ) -> Result<()> {
+        self.spend_allowance(from, spender, value)?;
+        self.transfer(from, to, value)
+    }
+
+    /// Creates `value` tokens and assigns them to `account`.
+    ///
+    /// ## Errors:
+    /// - `InvalidReceiver` if `account` is the zero account.
+    pub fn mint(&mut self, account: AccountId, value: u128) -> Result<()> {
+        if account == Self::zero_account() {
+            return Err(Error::InvalidReceiver { receiver: account });
+        }
+        self.total_supply.set(&(self.total_supply() + value));
+        let balance = self.balance_of(account);
+        self.balances.insert(account, &(balance + value));
+        ink::env::emit_event::<DefaultEnvironment, _>(Transfer {
+            from: Self::zero_account(),
+            to: account,
+            value,
+        });
+        Ok(())
+    }
+
+    /// Destroys `value` tokens of `account`.
+    ///
+    /// ## Errors:
+    /// - `InvalidSender` if `account` is the zero account.
+    /// - `InsufficientBalance` if `account` holds less than `value`.
+    pub fn burn(&mut self, account: AccountId, value: u128) -> Result<()> {
+        if account == Self::zero_account() {
+            return Err(Error::InvalidSender { sender: account });
+        }
+        let balance = self.balance_of(account);
+        if balance < value {
+            return Err(Error::InsufficientBalance {
+                sender: account,
+                balance,
+                needed: value,
+            });
+        }
+        self.total_supply.set(&(self.total_supply() - value));
+        self.balances.insert(account, &(balance - value));
+        ink::env::emit_event::<DefaultEnvironment, _>(Transfer {
+            from: account,
+            to: Self::zero_account(),
+            value,
+        });
+        Ok(())
+    }
+
+    /// Sets `value` as the allowance of `spender` over the tokens of `owner`.
+    ///
+    /// ## Errors:
+    /// - `InvalidApprover` if `owner` is the zero account.
+    /// - `InvalidSpender` if `spender` is the zero account.
+    pub fn approve(&mut self, owner: AccountId, spender: AccountId, value: u128) -> Result<()> {
+        if owner == Self::zero_account() {
+            return Err(Error::InvalidApprover { approver: owner });
+        }
+        if spender == Self::zero_account() {
+            return Err(Error::InvalidSpender { spender });
+        }
+        self.allowances.insert((owner, spender), &value);
+        ink::env::emit_event::<DefaultEnvironment, _>(Approval {
+            owner,
+            spender,
+            value,
+        });
+        Ok(())
+    }
+
+    /// Deducts `value` from the allowance of `spender` over the tokens of
+    /// `owner`. An allowance of `u128::MAX` is never decreased.
+    ///
+    /// ## Errors:
+    /// - `InsufficientAllowance` if the allowance is lower than `value`.
+    pub fn spend_allowance(
+        &mut self,
+        owner: AccountId,
+        spender: AccountId,
+        value: u128,
+    ) -> Result<()> {
+        let allowance = self.allowance(owner, spender);
+        if allowance == u128::MAX {
+            return Ok(());
+        }
+        if allowance < value {
+            return Err(Error::InsufficientAllowance {
+                spender,
+                allowance,
+                needed: value,
+            });
+        }
+        self.allowances
+            .insert((owner, spender), &(allowance - value));
+        Ok(())
+    }
+
+    /// The zero account, used as `from` on mint and `to` on burn.
+    pub fn zero_account() -> AccountId {
+        AccountId::from([0u8; 32])
+    }
+}
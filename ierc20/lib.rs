@@ -2,19 +2,23 @@
 
 use ink::primitives::AccountId;
 
+pub mod erc20_core;
+pub use erc20_core::Erc20Core;
+
 /// The IERC20 result type.
 pub type Result<T> = core::result::Result<T, Error>;
 
 /// Emitted when `value` tokens are moved from one account (`from`) to another (`to`).
 ///
-/// Note: `value` may be zero.
+/// Note: `value` may be zero. `from` is the zero account on mint and `to` is
+/// the zero account on burn.
 #[ink::event]
 pub struct Transfer {
     #[ink(topic)]
-    owner: AccountId,
+    pub from: AccountId,
     #[ink(topic)]
-    spender: AccountId,
-    value: u128,
+    pub to: AccountId,
+    pub value: u128,
 }
 
 /// Emitted when the allowance of a `spender` for an `owner` is set by a call to `approve`.
@@ -22,10 +26,10 @@ pub struct Transfer {
 #[ink::event]
 pub struct Approval {
     #[ink(topic)]
-    owner: AccountId,
+    pub owner: AccountId,
     #[ink(topic)]
-    spender: AccountId,
-    value: u128,
+    pub spender: AccountId,
+    pub value: u128,
 }
 
 /// A trait definition for an ERC-20 compatible token, following the IERC20 standard.
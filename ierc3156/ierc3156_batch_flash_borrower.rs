@@ -0,0 +1,60 @@
+//! Trait definition for a Flash Borrower contract receiving several tokens in a single loan.
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use crate::ierc3156_flash_borrower::Result;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+/// A trait for batch flash borrowing of ERC20 tokens, extending the IERC3156 standard.
+#[ink::trait_definition]
+pub trait IERC3156BatchFlashBorrower {
+    /// Batch flash loan callback.
+    ///
+    /// This function is called by the lender after the tokens of every leg have
+    /// been transferred. It verifies the caller, the initiator and that the loan
+    /// matches the one requested by `batch_flash_borrow`, decodes the action,
+    /// and executes custom logic depending on the action type.
+    ///
+    /// ## Parameters:
+    /// - `initiator`: The account that initiated the loan. Must be `self`.
+    /// - `tokens`: The addresses of the tokens that were lent.
+    /// - `amounts`: The amount borrowed of each token.
+    /// - `fees`: The fee charged by the lender for each token.
+    /// - `data`: Encoded arbitrary data, usually used to signal the type of action.
+    ///
+    /// ## Returns:
+    /// - A `bool` hash signaling successful execution of the callback.
+    #[ink(message)]
+    fn on_batch_flash_loan(
+        &self,
+        initiator: AccountId,
+        tokens: Vec<AccountId>,
+        amounts: Vec<u128>,
+        fees: Vec<u128>,
+        data: Vec<u8>,
+    ) -> Result<[u8; 32]>;
+
+    /// Initiates a batch flash loan from the trusted lender.
+    ///
+    /// Prepares the encoded action data, approves the lender for exactly the repayment
+    /// of every token, and requests a batch flash loan from the lender. The allowances
+    /// are reset to zero once the loan is over. The whole loan reverts unless the
+    /// borrower ends up at least `min_profits` richer in each token once every leg
+    /// is repaid. A `min_profits` of `None` turns the check off, so the borrower pays
+    /// the fees and any loss of the callback from its own balance.
+    ///
+    /// ## Parameters:
+    /// - `tokens`: The addresses of the tokens to borrow.
+    /// - `amounts`: The amount of each token to borrow.
+    /// - `data`: Encoded instructions for the callback, in the borrower's own format.
+    /// - `min_profits`: The minimum gain each leg must produce in its token, or `None`
+    ///   to accept any loss.
+    #[ink(message)]
+    fn batch_flash_borrow(
+        &mut self,
+        tokens: Vec<AccountId>,
+        amounts: Vec<u128>,
+        data: Vec<u8>,
+        min_profits: Option<Vec<u128>>,
+    ) -> Result<()>;
+}
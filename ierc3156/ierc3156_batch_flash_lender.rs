@@ -0,0 +1,42 @@
+//! Trait definition for a Flash Lender contract lending several tokens in a single loan.
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use crate::ierc3156_flash_lender::Result;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+/// A trait for batch flash lending of ERC20 tokens, extending the IERC3156 standard.
+#[ink::trait_definition]
+pub trait IERC3156BatchFlashLender {
+    /// Loan `amounts` of `tokens` to `receiver`, and take them back plus the
+    /// `batchFlashFee` of every leg after the callback.
+    ///
+    /// ## Params:
+    /// - `receiver`: The contract receiving the tokens.  
+    ///   Must implement the `IERC3156BatchFlashBorrower` interface.
+    /// - `tokens`: The loan currencies.
+    /// - `amounts`: The amount of each token lent, matching `tokens` by position.
+    /// - `data`: A data parameter to be passed on to the `receiver` for any custom use.
+    ///
+    /// ## Returns:
+    /// - `bool`: True if the flash loan succeeds.
+    #[ink(message)]
+    fn batch_flash_loan(
+        &mut self,
+        receiver: AccountId,
+        tokens: Vec<AccountId>,
+        amounts: Vec<u128>,
+        data: Vec<u8>,
+    ) -> Result<bool>;
+
+    /// The fees to be charged for a given batch loan.
+    ///
+    /// ## Params:
+    /// - `tokens`: The loan currencies.
+    /// - `amounts`: The amount of each token lent, matching `tokens` by position.
+    ///
+    /// ## Returns:
+    /// - `Vec<u128>`: The fee of each leg, to be charged on top of the returned principal.
+    #[ink(message)]
+    fn batch_flash_fee(&self, tokens: Vec<AccountId>, amounts: Vec<u128>) -> Result<Vec<u128>>;
+}
@@ -38,14 +38,27 @@ pub trait IERC3156FlashBorrower {
 
     /// Initiates a flash loan from the trusted lender.
     ///
-    /// Prepares the encoded action data, checks and increases allowance if necessary,
-    /// and requests a flash loan from the lender.
+    /// Prepares the encoded action data, approves the lender for exactly the repayment,
+    /// and requests a flash loan from the lender. The allowance is reset to zero once
+    /// the loan is over. The whole loan reverts unless the
+    /// borrower ends up at least `min_profit` tokens richer once `amount + fee` is repaid.
+    /// A `min_profit` of `None` turns the check off, so the borrower pays the fee and
+    /// any loss of the callback from its own balance.
     ///
     /// ## Parameters:
     /// - `token`: The address of the token to borrow.
     /// - `amount`: The amount of tokens to borrow.
+    /// - `data`: Encoded instructions for the callback, in the borrower's own format.
+    /// - `min_profit`: The minimum gain in `token` the loan must produce, or `None` to
+    ///   accept any loss.
     #[ink(message)]
-    fn flash_borrow(&self, token: AccountId, amount: u128) -> Result<()>;
+    fn flash_borrow(
+        &mut self,
+        token: AccountId,
+        amount: u128,
+        data: Vec<u8>,
+        min_profit: Option<u128>,
+    ) -> Result<()>;
 }
 
 /// The Flash Receiver error types.
@@ -62,4 +75,52 @@ pub enum Error {
     ERC3156LenderError(LenderError),
     /// Error related to ERC20.
     ERC20Error(ERC20Error),
+    /// Returned if a protocol called by the action rejected the call.
+    ActionFailed,
+    /// Returned if the caller is not allowed to perform the operation.
+    Unauthorized,
+    /// Returned if the requested strategy is not registered.
+    UnregisteredStrategy,
+    /// Returned if the strategy call failed.
+    StrategyFailed,
+    /// Returned if the call at the given position of a multicall failed.
+    CallFailed(u32),
+    /// Returned if the borrower cannot cover `amount + fee` after executing the loan.
+    InsufficientRepayment {
+        balance: u128,
+        needed: u128,
+    },
+    /// Returned if the loan left the borrower with less than its starting balance
+    /// plus the requested minimum profit.
+    InsufficientProfit {
+        balance: u128,
+        needed: u128,
+    },
+    /// Returned if the action cannot run on the loan it was requested with.
+    InvalidAction,
+    /// Returned if the per-leg arguments of a batch loan differ in length.
+    InconsistentBatchLength,
+    /// Returned if an arithmetic operation overflows, e.g. on a huge quoted fee.
+    Overflow,
+    /// Returned if a loan callback arrives while the borrower requested no loan.
+    NoPendingLoan,
+    /// Returned if the callback token differs from the requested one.
+    UnexpectedToken {
+        expected: AccountId,
+        received: AccountId,
+    },
+    /// Returned if the callback amount differs from the requested one.
+    UnexpectedAmount {
+        expected: u128,
+        received: u128,
+    },
+    /// Returned if the callback fee differs from the one quoted by the lender.
+    UnexpectedFee {
+        expected: u128,
+        received: u128,
+    },
+    /// Returned if the callback data differs from the data the loan was requested with.
+    UnexpectedData,
+    /// Returned if the callback legs differ in number from the requested batch loan.
+    UnexpectedLegs,
 }
@@ -7,6 +7,9 @@ use ink::primitives::AccountId;
 /// The Flash lender result type.
 pub type Result<T> = core::result::Result<T, Error>;
 
+/// Sentinel token id standing for the chain's native balance.
+pub const NATIVE_TOKEN: AccountId = AccountId([0xee; 32]);
+
 /// A trait for flash lending of ERC20 tokens, following the IERC3156 standard.
 #[ink::trait_definition]
 pub trait IERC3156FlashLender {
@@ -23,7 +26,7 @@ pub trait IERC3156FlashLender {
     /// - `bool`: True if the flash loan succeeds.
     #[ink(message)]
     fn flash_loan(
-        &self,
+        &mut self,
         receiver: AccountId,
         token: AccountId,
         amount: u128,
@@ -33,7 +36,7 @@ pub trait IERC3156FlashLender {
     /// The fee to be charged for a given loan.
     ///
     /// ## Params:
-    /// - `token`: The loan currency.
+    /// - `token`: The loan currency, or `NATIVE_TOKEN` for the native balance.
     /// - `amount`: The amount of tokens lent.
     ///
     /// ## Returns:
@@ -44,7 +47,7 @@ pub trait IERC3156FlashLender {
     /// The amount of currency available to be lent.
     ///
     /// ## Params:
-    /// - `token`: The loan currency.
+    /// - `token`: The loan currency, or `NATIVE_TOKEN` for the native balance.
     ///
     /// ## Returns:
     /// - `u128`: The amount of `token` that can be borrowed.
@@ -66,4 +69,28 @@ pub enum Error {
     ERC20Error(ERC20Error),
     /// Returned if error related to ERC3156Borrower happened.
     ERC3156BorrowerLoanError,
+    /// Returned if the caller is not allowed to perform the operation.
+    Unauthorized,
+    /// Returned if the liquidity provider holds fewer shares than requested.
+    InsufficientShares,
+    /// Returned if a deposit or withdrawal would mint or return nothing.
+    ZeroShares,
+    /// Returned if an arithmetic operation overflowed.
+    Overflow,
+    /// Returned if the tokens and amounts of a batch loan differ in length.
+    InconsistentBatchLength,
+    /// Returned if the requested amount exceeds `max_flash_loan`.
+    AmountExceedsMaxLoan,
+    /// Returned if the lender balance after the loan is lower than its balance
+    /// before the loan plus the fee.
+    RepaymentShortfall { balance: u128, needed: u128 },
+    /// Returned if a loan of the token is already in progress.
+    ReentrantLoan,
+    /// Returned if lending is paused for all tokens or for the requested token.
+    Paused,
+    /// Returned if the native value attached to the call differs from the amount
+    /// deposited.
+    InvalidTransferredValue,
+    /// Returned if adding a token that is already supported.
+    AlreadySupported,
 }
@@ -0,0 +1,33 @@
+//! Trait definition for a strategy run by a Flash Borrower with the borrowed funds.
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use crate::ierc3156_flash_borrower::Result;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+/// A trait for the logic executed by a flash borrower while it holds a loan.
+///
+/// A strategy is either called as a contract, in which case the borrower first
+/// transfers it the borrowed tokens and expects `amount + fee` of every token
+/// back before returning, or run through a delegate call in the borrower context,
+/// in which case the borrowed tokens are already at hand. Code run through a
+/// delegate call shares the borrower storage, so it must not declare storage
+/// of its own.
+#[ink::trait_definition]
+pub trait IFlashLoanStrategy {
+    /// Executes the strategy.
+    ///
+    /// ## Parameters:
+    /// - `tokens`: The addresses of the tokens that were lent.
+    /// - `amounts`: The amount borrowed of each token.
+    /// - `fees`: The fee charged by the lender for each token.
+    /// - `params`: Encoded strategy specific parameters.
+    #[ink(message)]
+    fn execute(
+        &self,
+        tokens: Vec<AccountId>,
+        amounts: Vec<u128>,
+        fees: Vec<u128>,
+        params: Vec<u8>,
+    ) -> Result<()>;
+}
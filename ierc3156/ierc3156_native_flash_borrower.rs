@@ -0,0 +1,33 @@
+//! Trait definition for a Flash Borrower contract receiving the chain's native balance.
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use crate::ierc3156_flash_borrower::Result;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+/// A trait for flash borrowing of the native balance, extending the IERC3156 standard.
+#[ink::trait_definition]
+pub trait IERC3156NativeFlashBorrower {
+    /// Native flash loan callback.
+    ///
+    /// This function is called by the lender with the borrowed native balance
+    /// attached as the transferred value. It must transfer `amount + fee` back
+    /// to the lender before returning.
+    ///
+    /// ## Parameters:
+    /// - `initiator`: The account that initiated the loan.
+    /// - `amount`: The amount of native balance borrowed.
+    /// - `fee`: The fee charged by the lender.
+    /// - `data`: Encoded arbitrary data, usually used to signal the type of action.
+    ///
+    /// ## Returns:
+    /// - A `bool` hash signaling successful execution of the callback.
+    #[ink(message, payable)]
+    fn on_native_flash_loan(
+        &mut self,
+        initiator: AccountId,
+        amount: u128,
+        fee: u128,
+        data: Vec<u8>,
+    ) -> Result<[u8; 32]>;
+}
@@ -0,0 +1,32 @@
+//! Trait definition for a Flash Lender contract lending the chain's native balance.
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use crate::ierc3156_flash_lender::Result;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+/// A trait for flash lending of the native balance, extending the IERC3156 standard.
+///
+/// Fees and loan limits are quoted by `IERC3156FlashLender::flash_fee` and
+/// `IERC3156FlashLender::max_flash_loan` for the `NATIVE_TOKEN` sentinel.
+#[ink::trait_definition]
+pub trait IERC3156NativeFlashLender {
+    /// Loan `amount` of native balance to `receiver`, and check that it was sent
+    /// back plus a `flashFee` after the callback.
+    ///
+    /// ## Params:
+    /// - `receiver`: The contract receiving the native balance.  
+    ///   Must implement the `IERC3156NativeFlashBorrower` interface.
+    /// - `amount`: The amount of native balance lent.
+    /// - `data`: A data parameter to be passed on to the `receiver` for any custom use.
+    ///
+    /// ## Returns:
+    /// - `bool`: True if the flash loan succeeds.
+    #[ink(message)]
+    fn native_flash_loan(
+        &mut self,
+        receiver: AccountId,
+        amount: u128,
+        data: Vec<u8>,
+    ) -> Result<bool>;
+}
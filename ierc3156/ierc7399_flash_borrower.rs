@@ -0,0 +1,44 @@
+//! Trait definition for a Flash Borrower callback compatible with `IERC7399FlashLender`.
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use crate::ierc3156_flash_borrower::Result;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+/// The selector of [`IERC7399FlashBorrower::on_flash`], to pass as the
+/// `callback_selector` of `IERC7399FlashLender::flash`.
+pub const ON_FLASH_SELECTOR: [u8; 4] = ink::selector_bytes!("IERC7399FlashBorrower::on_flash");
+
+/// A trait for the callback of an `IERC7399FlashLender` loan.
+///
+/// A lender may call back any message with the same signature, so implementing
+/// this trait is only needed to be reached through [`ON_FLASH_SELECTOR`].
+#[ink::trait_definition]
+pub trait IERC7399FlashBorrower {
+    /// ERC-7399 Flash loan callback.
+    ///
+    /// This function is called by the lender after the tokens have been
+    /// transferred to the loan receiver. It must transfer `amount + fee` to
+    /// `payment_receiver` before returning.
+    ///
+    /// ## Parameters:
+    /// - `initiator`: The account that initiated the loan.
+    /// - `payment_receiver`: The account the loan must be paid back to.
+    /// - `asset`: The address of the token that was lent.
+    /// - `amount`: The amount of tokens borrowed.
+    /// - `fee`: The fee charged by the lender.
+    /// - `data`: Arbitrary data passed on by the initiator.
+    ///
+    /// ## Returns:
+    /// - Arbitrary data handed back to the initiator by the lender.
+    #[ink(message)]
+    fn on_flash(
+        &self,
+        initiator: AccountId,
+        payment_receiver: AccountId,
+        asset: AccountId,
+        amount: u128,
+        fee: u128,
+        data: Vec<u8>,
+    ) -> Result<Vec<u8>>;
+}
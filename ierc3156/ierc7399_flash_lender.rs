@@ -0,0 +1,42 @@
+//! Trait definition for a Flash Lender contract modelled on `IERC7399`.
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use crate::ierc3156_flash_lender::Result;
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+
+/// A trait for flash lending of ERC20 tokens with a caller-chosen receiver and
+/// callback, following the IERC7399 standard.
+///
+/// Fees and loan limits are quoted by `IERC3156FlashLender::flash_fee` and
+/// `IERC3156FlashLender::max_flash_loan` of the same lender.
+#[ink::trait_definition]
+pub trait IERC7399FlashLender {
+    /// Loan `amount` of `asset` to `loan_receiver`, then call `callback_selector` on
+    /// `callback`, which must transfer `amount` plus the fee to the payment receiver
+    /// before returning.
+    ///
+    /// The callback message must have the signature of
+    /// `IERC7399FlashBorrower::on_flash`.
+    ///
+    /// ## Params:
+    /// - `loan_receiver`: The account receiving the tokens.
+    /// - `asset`: The loan currency.
+    /// - `amount`: The amount of tokens lent.
+    /// - `data`: A data parameter to be passed on to the callback for any custom use.
+    /// - `callback`: The contract called once the tokens are lent.
+    /// - `callback_selector`: The selector of the callback message.
+    ///
+    /// ## Returns:
+    /// - `Vec<u8>`: The data returned by the callback.
+    #[ink(message)]
+    fn flash(
+        &mut self,
+        loan_receiver: AccountId,
+        asset: AccountId,
+        amount: u128,
+        data: Vec<u8>,
+        callback: AccountId,
+        callback_selector: [u8; 4],
+    ) -> Result<Vec<u8>>;
+}
@@ -1,2 +1,9 @@
+pub mod ierc3156_batch_flash_borrower;
+pub mod ierc3156_batch_flash_lender;
 pub mod ierc3156_flash_borrower;
 pub mod ierc3156_flash_lender;
+pub mod ierc3156_flash_strategy;
+pub mod ierc3156_native_flash_borrower;
+pub mod ierc3156_native_flash_lender;
+pub mod ierc7399_flash_borrower;
+pub mod ierc7399_flash_lender;
@@ -0,0 +1,106 @@
+//! Trait definition for a collateralized lending market.
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+use ierc20::Error as ERC20Error;
+use ink::primitives::AccountId;
+
+/// The lending market result type.
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Prices are quoted in a common unit, scaled by this factor.
+pub const PRICE_PRECISION: u128 = 1_000_000;
+
+/// The position of an account: one collateral token and one debt token.
+///
+/// A token slot is cleared once its amount goes back to zero, so switching the
+/// collateral or debt token requires closing that side of the position first.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct Position {
+    pub collateral_token: Option<AccountId>,
+    pub collateral: u128,
+    pub debt_token: Option<AccountId>,
+    pub debt: u128,
+}
+
+/// A trait for depositing collateral, borrowing against it and liquidating
+/// unhealthy positions.
+#[ink::trait_definition]
+pub trait ILendingMarket {
+    /// Returns the price of `token`, scaled by `PRICE_PRECISION`. Zero if unlisted.
+    #[ink(message)]
+    fn price(&self, token: AccountId) -> u128;
+
+    /// Returns the position of `account`.
+    #[ink(message)]
+    fn position(&self, account: AccountId) -> Position;
+
+    /// Returns whether the debt of `account` is covered by its collateral.
+    #[ink(message)]
+    fn is_healthy(&self, account: AccountId) -> bool;
+
+    /// Deposits `amount` of `token`, pulled from the caller, as collateral.
+    #[ink(message)]
+    fn deposit_collateral(&mut self, token: AccountId, amount: u128) -> Result<()>;
+
+    /// Withdraws `amount` of the caller's collateral `token`.
+    ///
+    /// The position must stay healthy.
+    #[ink(message)]
+    fn withdraw_collateral(&mut self, token: AccountId, amount: u128) -> Result<()>;
+
+    /// Borrows `amount` of `token` against the caller's collateral.
+    ///
+    /// The position must stay healthy.
+    #[ink(message)]
+    fn borrow(&mut self, token: AccountId, amount: u128) -> Result<()>;
+
+    /// Repays `amount` of the caller's debt in `token`, pulled from the caller.
+    #[ink(message)]
+    fn repay(&mut self, token: AccountId, amount: u128) -> Result<()>;
+
+    /// Repays `amount` of the debt of an unhealthy `borrower` in `debt_token`,
+    /// pulled from the caller, and sends the caller the collateral worth the
+    /// repaid debt plus the liquidation bonus.
+    ///
+    /// ## Returns:
+    /// - The amount of collateral sent to the caller.
+    #[ink(message)]
+    fn liquidate(
+        &mut self,
+        borrower: AccountId,
+        debt_token: AccountId,
+        amount: u128,
+    ) -> Result<u128>;
+}
+
+/// The lending market error types.
+#[derive(Debug, PartialEq, Eq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum Error {
+    /// Returned if the token has no price.
+    UnlistedToken,
+    /// Returned if the amount is zero.
+    ZeroAmount,
+    /// Returned if the token differs from the collateral token of the position.
+    CollateralTokenMismatch,
+    /// Returned if the token differs from the debt token of the position.
+    DebtTokenMismatch,
+    /// Returned if withdrawing more collateral than deposited.
+    InsufficientCollateral,
+    /// Returned if repaying or liquidating more than the debt.
+    RepayExceedsDebt,
+    /// Returned if the operation would leave the position unhealthy.
+    Undercollateralized,
+    /// Returned if liquidating a healthy position.
+    PositionHealthy,
+    /// Returned if the market does not hold enough tokens to lend.
+    InsufficientLiquidity,
+    /// Returned if the caller is not allowed to perform the operation.
+    Unauthorized,
+    /// Returned if an arithmetic operation overflowed.
+    Overflow,
+    /// Error related to ERC20.
+    ERC20Error(ERC20Error),
+}